@@ -0,0 +1 @@
+ALTER TABLE courses ADD COLUMN launch_candidates TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+-- document order of SCOs within the manifest, used for continue/previous navigation
+ALTER TABLE scos ADD COLUMN sort_order INT NOT NULL DEFAULT 0;
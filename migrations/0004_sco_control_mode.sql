@@ -0,0 +1,5 @@
+-- imsss:controlMode per item (SCORM 2004 sequencing); IMS SS defaults
+ALTER TABLE scos
+  ADD COLUMN control_choice BOOLEAN NOT NULL DEFAULT true,
+  ADD COLUMN control_flow BOOLEAN NOT NULL DEFAULT false,
+  ADD COLUMN control_forward_only BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1 @@
+ALTER TABLE attempts ADD COLUMN learner_name TEXT;
@@ -0,0 +1 @@
+ALTER TABLE courses ADD COLUMN scorm_version TEXT NOT NULL DEFAULT '1.2';
@@ -0,0 +1,2 @@
+-- set when an instructor marked the attempt complete manually
+ALTER TABLE attempts ADD COLUMN instructor_override BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- accumulated cmi.core.session_time across finished sessions (seconds)
+ALTER TABLE attempts ADD COLUMN total_time_secs DOUBLE PRECISION NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- optional per-course allowlist of writable CMI elements (JSON array of names / "prefix*")
+ALTER TABLE courses ADD COLUMN allowed_elements JSONB;
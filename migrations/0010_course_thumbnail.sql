@@ -0,0 +1 @@
+ALTER TABLE courses ADD COLUMN thumbnail_href TEXT;
@@ -0,0 +1 @@
+ALTER TABLE courses ADD COLUMN min_session_seconds INT;
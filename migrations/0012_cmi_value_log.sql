@@ -0,0 +1,10 @@
+-- append-only history of every stored CMI write (cmi_values keeps only the latest)
+CREATE TABLE cmi_value_log (
+  id BIGSERIAL PRIMARY KEY,
+  attempt_id UUID NOT NULL REFERENCES attempts(id) ON DELETE CASCADE,
+  element TEXT NOT NULL,
+  value TEXT,
+  logged_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_cmi_value_log_attempt ON cmi_value_log(attempt_id, logged_at);
@@ -0,0 +1,18 @@
+-- one registration per course+learner run; each SCO launch under it is an attempt
+CREATE TABLE registrations (
+  id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  course_id UUID NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+  learner_id TEXT NOT NULL,
+  learner_name TEXT,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+ALTER TABLE attempts ADD COLUMN registration_id UUID REFERENCES registrations(id) ON DELETE CASCADE;
+
+-- existing attempts each become their own registration
+INSERT INTO registrations (id, course_id, learner_id, learner_name, created_at)
+  SELECT id, course_id, learner_id, learner_name, created_at FROM attempts;
+UPDATE attempts SET registration_id = id;
+
+ALTER TABLE attempts ALTER COLUMN registration_id SET NOT NULL;
+CREATE INDEX idx_attempts_registration ON attempts(registration_id);
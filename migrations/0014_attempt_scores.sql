@@ -0,0 +1,22 @@
+-- denormalized from cmi.core.score.* on commit, for reporting without touching cmi_values
+ALTER TABLE attempts
+  ADD COLUMN score_raw DOUBLE PRECISION,
+  ADD COLUMN score_min DOUBLE PRECISION,
+  ADD COLUMN score_max DOUBLE PRECISION,
+  ADD COLUMN score_scaled DOUBLE PRECISION;
+
+UPDATE attempts a SET
+  score_raw = (SELECT value::float8 FROM cmi_values
+               WHERE attempt_id = a.id AND element = 'cmi.core.score.raw'
+                 AND value ~ '^\s*-?[0-9]+(\.[0-9]+)?\s*$'),
+  score_min = (SELECT value::float8 FROM cmi_values
+               WHERE attempt_id = a.id AND element = 'cmi.core.score.min'
+                 AND value ~ '^\s*-?[0-9]+(\.[0-9]+)?\s*$'),
+  score_max = (SELECT value::float8 FROM cmi_values
+               WHERE attempt_id = a.id AND element = 'cmi.core.score.max'
+                 AND value ~ '^\s*-?[0-9]+(\.[0-9]+)?\s*$');
+
+-- same formula as runtime::scaled_score: min/max default to 0/100, clamped to 0..1
+UPDATE attempts SET score_scaled =
+  LEAST(1, GREATEST(0, (score_raw - COALESCE(score_min, 0)) / (COALESCE(score_max, 100) - COALESCE(score_min, 0))))
+WHERE score_raw IS NOT NULL AND COALESCE(score_max, 100) > COALESCE(score_min, 0);
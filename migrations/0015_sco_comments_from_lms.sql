@@ -0,0 +1 @@
+ALTER TABLE scos ADD COLUMN comments_from_lms TEXT;
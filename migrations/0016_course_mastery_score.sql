@@ -0,0 +1 @@
+ALTER TABLE courses ADD COLUMN mastery_score DOUBLE PRECISION;
@@ -0,0 +1,2 @@
+-- stable per-attempt seed for content randomization (volatile default: one per row)
+ALTER TABLE attempts ADD COLUMN random_seed BIGINT NOT NULL DEFAULT floor(random() * 2147483647)::bigint;
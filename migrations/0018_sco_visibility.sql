@@ -0,0 +1,2 @@
+-- <item isvisible="false">: hidden from the menu, still launchable
+ALTER TABLE scos ADD COLUMN is_visible BOOLEAN NOT NULL DEFAULT true;
@@ -0,0 +1,8 @@
+-- free-form catalog tags, stored lowercase
+CREATE TABLE IF NOT EXISTS course_tags (
+  course_id UUID NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+  tag TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (course_id, tag)
+);
+CREATE INDEX IF NOT EXISTS idx_course_tags_tag ON course_tags(tag);
@@ -0,0 +1,2 @@
+-- optional retry cap per learner; NULL = unlimited
+ALTER TABLE courses ADD COLUMN max_attempts INT;
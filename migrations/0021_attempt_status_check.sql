@@ -0,0 +1,3 @@
+-- attempts.status vocabulary; mirrors models::AttemptStatus
+ALTER TABLE attempts ADD CONSTRAINT attempts_status_check
+  CHECK (status IN ('in_progress', 'completed', 'suspended', 'timed_out', 'expired', 'launch_error'));
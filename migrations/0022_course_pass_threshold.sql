@@ -0,0 +1,2 @@
+-- operator pass bar; overrides mastery_score when set
+ALTER TABLE courses ADD COLUMN pass_threshold DOUBLE PRECISION;
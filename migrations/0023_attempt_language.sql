@@ -0,0 +1,2 @@
+-- learner's preferred language, seeded into the *_preference.language element
+ALTER TABLE attempts ADD COLUMN language TEXT;
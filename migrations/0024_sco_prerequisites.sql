@@ -0,0 +1,2 @@
+-- SCORM 1.2 adlcp:prerequisites expression (item identifiers with & | ~ and parentheses)
+ALTER TABLE scos ADD COLUMN prerequisites TEXT;
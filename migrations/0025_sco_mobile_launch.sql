@@ -0,0 +1,2 @@
+-- sibling '<name>.mobile.<ext>' launch file, chosen by the player for mobile devices
+ALTER TABLE scos ADD COLUMN mobile_launch_href TEXT;
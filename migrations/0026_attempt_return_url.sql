@@ -0,0 +1,2 @@
+-- where the player/host sends the learner after finish
+ALTER TABLE attempts ADD COLUMN return_url TEXT;
@@ -0,0 +1,2 @@
+-- <adlcp:datafromlms>, served read-only as cmi.launch_data; items inherit the organization's
+ALTER TABLE scos ADD COLUMN launch_data TEXT;
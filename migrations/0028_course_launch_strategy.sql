@@ -0,0 +1,3 @@
+-- which SCO the player opens for attempts without a sco_id; mirrors models::LaunchStrategy
+ALTER TABLE courses ADD COLUMN default_launch_strategy TEXT NOT NULL DEFAULT 'manifest_default'
+  CHECK (default_launch_strategy IN ('manifest_default', 'first_sco', 'resume'));
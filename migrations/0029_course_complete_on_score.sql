@@ -0,0 +1,2 @@
+-- treat a committed raw score as completion when the SCO never sets lesson_status
+ALTER TABLE courses ADD COLUMN complete_on_score BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- opaque integration metadata (assignment id, cohort, ...) echoed with the attempt
+ALTER TABLE attempts ADD COLUMN metadata JSONB;
@@ -0,0 +1,3 @@
+-- LOM <general> description and keywords from the manifest-level <metadata>
+ALTER TABLE courses ADD COLUMN description TEXT;
+ALTER TABLE courses ADD COLUMN keywords TEXT[] NOT NULL DEFAULT '{}';
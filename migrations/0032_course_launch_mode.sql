@@ -0,0 +1,3 @@
+-- how the player presents the SCO; mirrors models::LaunchMode
+ALTER TABLE courses ADD COLUMN launch_mode TEXT NOT NULL DEFAULT 'frame'
+  CHECK (launch_mode IN ('frame', 'popup'));
@@ -0,0 +1,7 @@
+-- compressed suspend_data is flagged here instead of by a value prefix, so a
+-- plaintext value that happens to start with `gz64:` reads back unchanged
+ALTER TABLE cmi_values ADD COLUMN compressed BOOLEAN NOT NULL DEFAULT false;
+
+-- rows written by the prefix scheme
+UPDATE cmi_values SET compressed = true, value = substr(value, 6)
+WHERE element = 'cmi.suspend_data' AND value LIKE 'gz64:%';
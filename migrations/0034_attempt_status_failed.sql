@@ -0,0 +1,4 @@
+-- `failed`: finished with cmi.core.lesson_status = failed; mirrors models::AttemptStatus
+ALTER TABLE attempts DROP CONSTRAINT attempts_status_check;
+ALTER TABLE attempts ADD CONSTRAINT attempts_status_check
+  CHECK (status IN ('in_progress', 'completed', 'failed', 'suspended', 'timed_out', 'expired', 'launch_error'));
@@ -1,11 +1,75 @@
-use anyhow::Result;
-use sqlx::{Pool, Postgres};
-use std::env;
+use anyhow::{bail, Result};
+use sqlx::{migrate::MigrateError, Connection, Pool, Postgres};
+use std::time::{Duration, Instant};
 
 pub type Db = Pool<Postgres>;
 
-pub async fn connect() -> Result<Db> {
-    let url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-    Ok(Pool::<Postgres>::connect(&url).await?)
+pub async fn connect(url: &str) -> Result<Db> {
+    Ok(Pool::<Postgres>::connect(url).await?)
 }
 
+/// Run the embedded migrations. Replicas starting together queue on sqlx's
+/// advisory lock; each try waits at most 5s for a lock (lock_timeout) and is
+/// retried until `max_wait`, so a replica behind the leader logs instead of
+/// hanging and then finds the migrations applied.
+pub async fn migrate(db: &Db, max_wait: Duration) -> Result<()> {
+    // crate-relative path for sqlx migrations
+    let migrator = sqlx::migrate!("./migrations");
+    let deadline = Instant::now() + max_wait;
+    loop {
+        // a connection of its own: closing it drops the session-level advisory
+        // lock even when a failed run never released it
+        let mut conn = db.acquire().await?.detach();
+        sqlx::query("SET lock_timeout = '5s'")
+            .execute(&mut conn)
+            .await?;
+        let res = migrator.run_direct(&mut conn).await;
+        let _ = conn.close().await;
+        match res {
+            Ok(()) => return Ok(()),
+            Err(e) if is_lock_timeout(&e) => {
+                if Instant::now() >= deadline {
+                    bail!(
+                        "gave up waiting for the migration lock after {}s",
+                        max_wait.as_secs()
+                    );
+                }
+                tracing::info!("migrations are locked (another replica is migrating); retrying");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// 55P03 lock_not_available, raised when lock_timeout expires
+fn is_lock_timeout(e: &MigrateError) -> bool {
+    match e {
+        MigrateError::Execute(sqlx::Error::Database(db)) => db.code().as_deref() == Some("55P03"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = false)]
+    async fn concurrent_migrations_both_succeed(db: Db) {
+        let wait = Duration::from_secs(60);
+        let (a, b) = tokio::join!(migrate(&db, wait), migrate(&db, wait));
+        a.unwrap();
+        b.unwrap();
+
+        let applied: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM _sqlx_migrations WHERE success")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(
+            applied as usize,
+            sqlx::migrate!("./migrations").iter().count()
+        );
+        // a replica starting later finds nothing left to do
+        migrate(&db, wait).await.unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+// Package download for `POST /api/courses/upload_from_url`.
+// The host is resolved once and the request is pinned to those addresses, so a
+// DNS answer cannot switch to an internal address between the check and the fetch.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("{0}")]
+    Blocked(String),
+    #[error("package exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("{0}")]
+    Upstream(String),
+}
+
+/// Download `url` (http/https, no redirects) into memory, refusing non-public
+/// addresses unless the host is in `allowlist`, and bodies over `max_bytes`.
+pub async fn fetch_package(
+    url: &str,
+    max_bytes: usize,
+    allowlist: &[String],
+) -> Result<Vec<u8>, FetchError> {
+    let url =
+        reqwest::Url::parse(url).map_err(|e| FetchError::Invalid(format!("invalid url: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(FetchError::Invalid("url must be http or https".into()));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| FetchError::Invalid("url has no host".into()))?
+        .trim_matches(['[', ']'])
+        .to_ascii_lowercase();
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| FetchError::Upstream(format!("cannot resolve {}: {}", host, e)))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(FetchError::Upstream(format!("cannot resolve {}", host)));
+    }
+    let allowlisted = allowlist.contains(&host);
+    if !allowlisted {
+        if let Some(addr) = addrs.iter().find(|a| !is_public_ip(a.ip())) {
+            return Err(FetchError::Blocked(format!(
+                "{} resolves to a non-public address ({})",
+                host,
+                addr.ip()
+            )));
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(120))
+        .resolve_to_addrs(&host, &addrs)
+        .build()
+        .map_err(|e| FetchError::Upstream(e.to_string()))?;
+    let mut res = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| FetchError::Upstream(format!("download failed: {}", e)))?;
+    if !res.status().is_success() {
+        return Err(FetchError::Upstream(format!(
+            "upstream returned {}",
+            res.status()
+        )));
+    }
+
+    let content_type = res
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase()
+        });
+    if let Some(ct) = content_type {
+        let zip_like = matches!(
+            ct.as_str(),
+            "application/zip"
+                | "application/x-zip-compressed"
+                | "application/octet-stream"
+                | "binary/octet-stream"
+        );
+        if !zip_like {
+            return Err(FetchError::Invalid(format!(
+                "unexpected content-type {:?}; expected a zip",
+                ct
+            )));
+        }
+    }
+    if res
+        .content_length()
+        .is_some_and(|len| len > max_bytes as u64)
+    {
+        return Err(FetchError::TooLarge(max_bytes));
+    }
+
+    // Content-Length may be absent or wrong; enforce the cap while reading
+    let mut bytes = Vec::new();
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .map_err(|e| FetchError::Upstream(format!("download failed: {}", e)))?
+    {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(FetchError::TooLarge(max_bytes));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Globally routable unicast address: not loopback, private, link-local,
+/// shared (CGNAT), documentation, unspecified, broadcast or multicast.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(v6),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00 // unique local
+        || (first & 0xffc0) == 0xfe80) // link-local
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_globally_routable_addresses_are_public() {
+        for public in [
+            "93.184.216.34",
+            "8.8.8.8",
+            "2606:4700::1111",
+            "::ffff:1.1.1.1",
+        ] {
+            assert!(is_public_ip(public.parse().unwrap()), "{public}");
+        }
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "224.0.0.1",
+            "192.0.2.1",
+            "::1",
+            "::",
+            "fc00::1",
+            "fd12::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_ip(private.parse().unwrap()), "{private}");
+        }
+    }
+
+    #[tokio::test]
+    async fn non_public_hosts_are_blocked_before_any_request() {
+        let err = fetch_package("http://127.0.0.1:9/course.zip", 1024, &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FetchError::Blocked(_)), "{err}");
+        let err = fetch_package("ftp://example.com/course.zip", 1024, &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FetchError::Invalid(_)), "{err}");
+    }
+}
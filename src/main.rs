@@ -1,15 +1,25 @@
-use axum::{routing::{get}, Router};
-use std::env;
+use axum::extract::DefaultBodyLimit;
+use axum::{http::header, middleware, routing::get, Router};
+use std::{env, time::Duration};
 use tokio::net::TcpListener;
-use tower_http::{trace::TraceLayer, cors::{Any, CorsLayer}};
+use tower_http::{
+    cors::{Any, CorsLayer},
+    trace::TraceLayer,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use axum::extract::DefaultBodyLimit;
 
+mod config;
 mod db;
+mod fetch;
+mod manifest;
 mod models;
+mod player;
+mod ratelimit;
+mod repo;
+mod retention;
 mod routes;
-mod manifest;
 mod runtime;
+mod server;
 mod util;
 
 #[tokio::main]
@@ -17,28 +27,67 @@ async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
-            env::var("RUST_LOG").unwrap_or_else(|_| "rustiscorm_runtime=info,axum=info".into())
+            env::var("RUST_LOG").unwrap_or_else(|_| "rustiscorm_runtime=info,axum=info".into()),
         ))
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let pool = db::connect().await?;
-    // crate-relative path for sqlx migrations
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    let config = config::Config::from_env()?;
+    tracing::info!("config: {}", config.summary());
+
+    let pool = db::connect(&config.database_url).await?;
+    db::migrate(&pool, Duration::from_secs(config.migration_wait_secs)).await?;
 
+    if config.retention_days > 0 {
+        retention::spawn(
+            pool.clone(),
+            retention::RetentionPolicy {
+                days: config.retention_days,
+                interval: Duration::from_secs(config.retention_interval_secs),
+                purge_log: config.retention_purge_log,
+            },
+        );
+    }
+
+    let port = config.port;
+    let limits = server::ServerLimits::from_config(&config);
+    let tls = server::tls_from_config(&config)?;
+    let body_limit = config.max_upload_bytes;
+    let timeouts = routes::Timeouts {
+        default: Duration::from_secs(config.request_timeout_secs),
+        upload: Duration::from_secs(config.upload_timeout_secs),
+    };
     let app = Router::new()
         .route("/health", get(|| async { "ok" }))
-        .merge(routes::router(pool.clone()))
-        .layer(DefaultBodyLimit::max(200 * 1024 * 1024))
+        .merge(routes::router(routes::AppState::new(pool.clone(), config)))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .layer(middleware::map_response_with_state(
+            body_limit,
+            routes::payload_too_large_json,
+        ))
+        .layer(middleware::from_fn_with_state(
+            timeouts,
+            routes::request_timeout,
+        ))
         .layer(TraceLayer::new_for_http())
-        .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any));
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any)
+                // cross-origin media players read these to seek within /content assets
+                .expose_headers([
+                    header::ACCEPT_RANGES,
+                    header::CONTENT_RANGE,
+                    header::CONTENT_LENGTH,
+                ]),
+        );
 
-    let port: u16 = env::var("PORT").ok().and_then(|s| s.parse().ok()).unwrap_or(8081);
     let addr = format!("0.0.0.0:{}", port);
     let listener = TcpListener::bind(addr).await?;
-    tracing::info!("listening on http://0.0.0.0:{}", port);
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    tracing::info!("listening on {}://0.0.0.0:{}", scheme, port);
 
-    axum::serve(listener, app).await?;
+    server::serve(listener, app, limits, tls).await?;
     Ok(())
 }
-
@@ -1,14 +1,143 @@
+use crate::util;
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 use walkdir::WalkDir;
 
 #[derive(Debug, Clone)]
 pub struct ParsedManifest {
     pub default_launch: String,
-    // (sco_identifier, href, parameters)
-    pub scos: Vec<(String, String, Option<String>)>,
+    // ordered fallbacks: default item, first SCO, first resource (deduplicated)
+    pub launch_candidates: Vec<String>,
+    pub scos: Vec<ParsedSco>,
+    pub scorm_version: ScormVersion,
+    // package-relative image for catalogs (declared in metadata or a well-known file)
+    pub thumbnail_href: Option<String>,
+    // (organization identifier, launch href of its first item), in document order
+    pub organizations: Vec<(String, String)>,
+    // set when the uploader pins an organization; the manifest's `default` is not recorded
+    pub org_identifier: Option<String>,
+    // first `<adlcp:masteryscore>` (SCORM 1.2, 0-100)
+    pub mastery_score: Option<f64>,
+    // every <resource> in document order, with dependencies expanded
+    pub resources: Vec<ParsedResource>,
+    // problems the parser worked around, e.g. items dropped for a dangling identifierref
+    pub warnings: Vec<String>,
+    pub lom: LomGeneral,
+}
+
+/// LOM `<general>` fields of the manifest-level `<metadata>` (inline LOM only;
+/// `<adlcp:location>` files are not followed). The first string of each field wins.
+#[derive(Debug, Clone, Default)]
+pub struct LomGeneral {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum LomField {
+    Title,
+    Description,
+    Keyword,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedResource {
+    pub identifier: String,
+    pub href: Option<String>,
+    // own <file> hrefs followed by those of its <dependency> resources (transitively), deduplicated
+    pub files: Vec<String>,
+}
+
+impl ParsedManifest {
+    /// First launch href (default or any SCO's) that is an absolute URL.
+    pub fn external_launch(&self) -> Option<&str> {
+        std::iter::once(&self.default_launch)
+            .chain(self.scos.iter().map(|s| &s.href))
+            .find(|href| util::is_absolute_url(href))
+            .map(String::as_str)
+    }
+
+    /// Launch from `org` instead of the manifest's default organization.
+    /// Returns false when the package has no such organization with a launchable item.
+    pub fn pin_organization(&mut self, org: &str) -> bool {
+        let Some((_, href)) = self.organizations.iter().find(|(id, _)| id == org) else {
+            return false;
+        };
+        let href = href.clone();
+        self.launch_candidates.retain(|c| *c != href);
+        self.launch_candidates.insert(0, href.clone());
+        self.default_launch = href;
+        self.org_identifier = Some(org.to_string());
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScormVersion {
+    #[default]
+    Scorm12,
+    Scorm2004,
+}
+
+impl ScormVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScormVersion::Scorm12 => "1.2",
+            ScormVersion::Scorm2004 => "2004",
+        }
+    }
+
+    // <schemaversion> values: "1.2", "CAM 1.3", "2004 3rd Edition", "2004 4th Edition", ...
+    fn from_schemaversion(v: &str) -> Self {
+        if v.contains("2004") || v.contains("1.3") {
+            ScormVersion::Scorm2004
+        } else {
+            ScormVersion::Scorm12
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedSco {
+    pub identifier: String,
+    pub href: String,
+    pub parameters: Option<String>,
+    pub control_mode: ControlMode,
+    // isvisible="false" hides the item from learner menus
+    pub visible: bool,
+    // <adlcp:prerequisites> expression over item identifiers
+    pub prerequisites: Option<String>,
+    // identifier of the <resource> the item references; None for synthetic manifests
+    pub resource: Option<String>,
+    // `<stem>.mobile.<ext>` next to the launch file, when the package ships one
+    pub mobile_href: Option<String>,
+    // <adlcp:datafromlms>, the item's own or else its organization's
+    pub launch_data: Option<String>,
+}
+
+/// `<imsss:controlMode>` flags for an item; defaults follow IMS SS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlMode {
+    pub choice: bool,
+    pub flow: bool,
+    pub forward_only: bool,
+}
+
+impl Default for ControlMode {
+    fn default() -> Self {
+        ControlMode {
+            choice: true,
+            flow: false,
+            forward_only: false,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -17,26 +146,348 @@ pub enum MfErr {
     Missing,
     #[error("failed to parse manifest")]
     Parse,
+    #[error("imsmanifest.xml exceeds {0} bytes")]
+    TooLarge(u64),
+    #[error("manifest items nested deeper than {MAX_ITEM_DEPTH} levels")]
+    TooDeep,
 }
 
-pub fn extract_zip_to_dir(bytes: &[u8], out_dir: &PathBuf) -> anyhow::Result<()> {
-    std::fs::create_dir_all(out_dir)?;
+/// Deepest `<item>` nesting accepted; real packages rarely exceed a handful.
+pub const MAX_ITEM_DEPTH: usize = 64;
+
+/// What extraction does with symlink entries (`ZIP_SYMLINKS`). They are never
+/// materialized either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Leave the entry out and keep extracting.
+    Skip,
+    /// Fail the upload.
+    Reject,
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "reject" => Ok(SymlinkPolicy::Reject),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What extraction does with file entries whose names differ only in case
+/// (`ZIP_CASE_COLLISIONS`); on a case-insensitive filesystem the later one
+/// overwrites the earlier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseCollisionPolicy {
+    /// Log a warning and extract both.
+    Warn,
+    /// Fail the upload.
+    Reject,
+}
+
+impl std::str::FromStr for CaseCollisionPolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "warn" => Ok(CaseCollisionPolicy::Warn),
+            "reject" => Ok(CaseCollisionPolicy::Reject),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Knobs for `extract_zip_to_dir`.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractOptions {
+    pub symlinks: SymlinkPolicy,
+    pub case_collisions: CaseCollisionPolicy,
+    // write into a target directory that already holds files
+    pub overwrite: bool,
+    // per entry: path components below the package root, and bytes of the
+    // resulting path including `out_dir`
+    pub max_path_depth: usize,
+    pub max_path_len: usize,
+}
+
+#[derive(Error, Debug)]
+pub enum ExtractErr {
+    #[error("package contains a symlink entry: {0}")]
+    Symlink(String),
+    #[error("package entry escapes the package root: {0}")]
+    UnsafePath(String),
+    #[error("package entries {0} and {1} differ only in case")]
+    CaseCollision(String, String),
+    #[error("package entry is nested deeper than {1} directories: {0}")]
+    PathTooDeep(String, usize),
+    #[error("package entry path is longer than {1} bytes once extracted: {0}")]
+    PathTooLong(String, usize),
+    #[error("extraction target {} already holds files", .0.display())]
+    TargetNotEmpty(PathBuf),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl ExtractErr {
+    /// Problems with the package itself, as opposed to I/O or a corrupt archive.
+    pub fn is_unsafe_entry(&self) -> bool {
+        matches!(
+            self,
+            ExtractErr::Symlink(_)
+                | ExtractErr::UnsafePath(_)
+                | ExtractErr::CaseCollision(..)
+                | ExtractErr::PathTooDeep(..)
+                | ExtractErr::PathTooLong(..)
+        )
+    }
+}
+
+/// Unix file-type bits of a symlink in a zip entry's external attributes.
+const S_IFLNK: u32 = 0o120000;
+const S_IFMT: u32 = 0o170000;
+
+/// Extract into `out_dir`, returning the number of regular files written.
+/// Entries whose names leave `out_dir` are rejected; symlink entries and
+/// case-colliding names follow `opts`. A non-empty `out_dir` is refused unless
+/// `opts.overwrite`, so two packages never mix. Entry names are checked against
+/// the path limits before anything is written.
+pub fn extract_zip_to_dir(
+    bytes: &[u8],
+    out_dir: &PathBuf,
+    opts: ExtractOptions,
+) -> Result<usize, ExtractErr> {
+    if !opts.overwrite && out_dir.is_dir() && std::fs::read_dir(out_dir)?.next().is_some() {
+        return Err(ExtractErr::TargetNotEmpty(out_dir.clone()));
+    }
     let reader = std::io::Cursor::new(bytes);
     let mut zip = zip::ZipArchive::new(reader)?;
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i)?;
+        let Some(rel) = file.enclosed_name() else {
+            return Err(ExtractErr::UnsafePath(file.name().to_string()));
+        };
+        if rel.components().count() > opts.max_path_depth {
+            return Err(ExtractErr::PathTooDeep(
+                file.name().to_string(),
+                opts.max_path_depth,
+            ));
+        }
+        if out_dir.join(rel).as_os_str().len() > opts.max_path_len {
+            return Err(ExtractErr::PathTooLong(
+                file.name().to_string(),
+                opts.max_path_len,
+            ));
+        }
+    }
+    std::fs::create_dir_all(out_dir)?;
+    let mut files = 0;
+    // lowercased file path -> first entry name seen with it
+    let mut folded: HashMap<String, String> = HashMap::new();
     for i in 0..zip.len() {
         let mut file = zip.by_index(i)?;
-        let outpath = out_dir.join(file.name());
+        let Some(rel) = file.enclosed_name().map(Path::to_path_buf) else {
+            return Err(ExtractErr::UnsafePath(file.name().to_string()));
+        };
+        if file.unix_mode().is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            match opts.symlinks {
+                SymlinkPolicy::Skip => {
+                    tracing::debug!("skipping symlink entry {}", file.name());
+                    continue;
+                }
+                SymlinkPolicy::Reject => return Err(ExtractErr::Symlink(file.name().to_string())),
+            }
+        }
+        let outpath = out_dir.join(&rel);
         if file.name().ends_with('/') {
             std::fs::create_dir_all(&outpath)?;
             continue;
         }
+        let name = rel.to_string_lossy().replace('\\', "/");
+        match folded.get(&name.to_lowercase()) {
+            Some(first) if *first != name => match opts.case_collisions {
+                CaseCollisionPolicy::Warn => tracing::warn!(
+                    "{}: zip entries {} and {} differ only in case; one is lost on case-insensitive filesystems",
+                    out_dir.display(),
+                    first,
+                    name
+                ),
+                CaseCollisionPolicy::Reject => return Err(ExtractErr::CaseCollision(first.clone(), name)),
+            },
+            Some(_) => {}
+            None => {
+                folded.insert(name.to_lowercase(), name);
+            }
+        }
         if let Some(parent) = outpath.parent() {
             std::fs::create_dir_all(parent)?;
         }
         let mut outfile = std::fs::File::create(&outpath)?;
         std::io::copy(&mut file, &mut outfile)?;
+        files += 1;
+    }
+    Ok(files)
+}
+
+/// Zip every regular file under `dir` into `out`, named relative to `dir`; the
+/// inverse of `extract_zip_to_dir`. Blob symlinks left by `dedup_into_blobs` are
+/// followed, so the archive holds their content. Returns the number of files.
+pub fn zip_dir(dir: &Path, out: &Path) -> zip::result::ZipResult<usize> {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(out)?);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut files = 0;
+    for entry in WalkDir::new(dir).follow_links(true).sort_by_file_name() {
+        let entry = entry.map_err(std::io::Error::from)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(rel) = entry.path().strip_prefix(dir) else {
+            continue;
+        };
+        let name = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(name, options)?;
+        std::io::copy(&mut std::fs::File::open(entry.path())?, &mut zip)?;
+        files += 1;
+    }
+    zip.finish()?;
+    Ok(files)
+}
+
+/// Remove a leading UTF-8 BOM from extracted `.js`/`.css`/`.html`/`.htm` files;
+/// a BOM in front of script can make it fail silently in the SCO frame.
+/// Returns the number of files rewritten.
+pub fn strip_text_boms(dir: &Path) -> std::io::Result<usize> {
+    const BOM: &[u8] = b"\xEF\xBB\xBF";
+    let mut stripped = 0;
+    for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        let textual = entry.file_type().is_file()
+            && path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                matches!(
+                    e.to_ascii_lowercase().as_str(),
+                    "js" | "css" | "html" | "htm"
+                )
+            });
+        if !textual {
+            continue;
+        }
+        let bytes = fs::read(path)?;
+        if let Some(rest) = bytes.strip_prefix(BOM) {
+            fs::write(path, rest)?;
+            stripped += 1;
+        }
+    }
+    Ok(stripped)
+}
+
+/// Re-encode extracted `.html`/`.htm` files that are not valid UTF-8 (legacy
+/// `windows-1252` and the like, detected with chardetng) to UTF-8, pointing any
+/// declared charset at utf-8 or adding a `<meta charset>` after `<head>`.
+/// Returns the number of files rewritten.
+pub fn transcode_legacy_html(dir: &Path) -> std::io::Result<usize> {
+    let mut rewritten = 0;
+    for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        let html = entry.file_type().is_file()
+            && path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "html" | "htm"));
+        if !html {
+            continue;
+        }
+        let bytes = fs::read(path)?;
+        if std::str::from_utf8(&bytes).is_ok() {
+            continue;
+        }
+        let mut detector = chardetng::EncodingDetector::new();
+        detector.feed(&bytes, true);
+        let encoding = detector.guess(None, true);
+        let (text, _, _) = encoding.decode(&bytes);
+        fs::write(path, declare_utf8_charset(&text))?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Move every regular file under `dir` into the content-addressed store
+/// `<blob_dir>/<sha256>` and leave a link in its place (a symlink on Unix, else a
+/// hard link or, failing that, a copy), so identical files across courses share
+/// one copy on disk. Must run after any in-place rewrite of the extracted tree.
+/// Returns how many files were already stored.
+pub fn dedup_into_blobs(dir: &Path, blob_dir: &Path) -> std::io::Result<usize> {
+    use sha2::{Digest, Sha256};
+    fs::create_dir_all(blob_dir)?;
+    // absolute link targets: the store is not under the course tree
+    let blob_dir = blob_dir.canonicalize()?;
+    let files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    let mut reused = 0;
+    for path in files {
+        let digest = Sha256::digest(fs::read(&path)?);
+        let hash: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        let blob = blob_dir.join(&hash);
+        if blob.is_file() {
+            fs::remove_file(&path)?;
+            reused += 1;
+        } else {
+            // rename is atomic; a concurrent upload of the same bytes just replaces it
+            fs::rename(&path, &blob)?;
+        }
+        link_blob(&blob, &path)?;
+    }
+    Ok(reused)
+}
+
+#[cfg(unix)]
+fn link_blob(blob: &Path, path: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(blob, path)
+}
+
+#[cfg(not(unix))]
+fn link_blob(blob: &Path, path: &Path) -> std::io::Result<()> {
+    // hard links need the same volume; a copy still works, without the saving
+    fs::hard_link(blob, path).or_else(|_| fs::copy(blob, path).map(|_| ()))
+}
+
+/// Point the document's charset declaration at utf-8, adding one if missing.
+fn declare_utf8_charset(html: &str) -> String {
+    // ASCII lowercasing keeps byte offsets aligned with `html`
+    let lower = html.to_ascii_lowercase();
+    let mut search = 0;
+    while let Some(at) = lower[search..].find("<meta").map(|i| i + search) {
+        let end = lower[at..].find('>').map_or(lower.len(), |i| at + i);
+        if let Some(cs) = lower[at..end]
+            .find("charset=")
+            .map(|i| at + i + "charset=".len())
+        {
+            let quoted = lower[cs..].starts_with(['"', '\'']);
+            let start = if quoted { cs + 1 } else { cs };
+            let stop = lower[start..end]
+                .find(|c: char| c == '"' || c == '\'' || c == ';' || c == '/' || c.is_whitespace())
+                .map_or(end, |i| start + i);
+            return format!("{}utf-8{}", &html[..start], &html[stop..]);
+        }
+        search = end;
+    }
+    match lower
+        .find("<head")
+        .and_then(|h| lower[h..].find('>').map(|i| h + i + 1))
+    {
+        Some(pos) => format!("{}<meta charset=\"utf-8\">{}", &html[..pos], &html[pos..]),
+        None => format!("<meta charset=\"utf-8\">{}", html),
     }
-    Ok(())
 }
 
 pub fn find_manifest(dir: &PathBuf) -> Result<PathBuf, MfErr> {
@@ -54,41 +505,138 @@ struct ResourceInfo {
     href: Option<String>,
     files: Vec<String>,
     scormtype: Option<String>,
+    // <dependency identifierref> targets
+    dependencies: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct ItemInfo {
+    identifier: String,
+    identifierref: String,
+    parameters: Option<String>,
+    control_mode: ControlMode,
+    visible: bool,
+    prerequisites: Option<String>,
+    organization: Option<String>,
+    data_from_lms: Option<String>,
 }
 
-pub fn parse_manifest(path: &PathBuf) -> Result<ParsedManifest, MfErr> {
+/// Parse `imsmanifest.xml`, refusing files over `max_bytes` before reading them.
+pub fn parse_manifest(path: &PathBuf, max_bytes: u64) -> Result<ParsedManifest, MfErr> {
+    let len = fs::metadata(path).map_err(|_| MfErr::Missing)?.len();
+    if len > max_bytes {
+        return Err(MfErr::TooLarge(max_bytes));
+    }
     let xml = fs::read_to_string(path).map_err(|_| MfErr::Missing)?;
     let mut reader = Reader::from_str(&xml);
     reader.trim_text(true);
 
     let mut buf = Vec::new();
 
-    // resources: resource identifier -> info
+    // resources: resource identifier -> info, plus identifiers in document order
     let mut resources: HashMap<String, ResourceInfo> = HashMap::new();
+    let mut resource_order: Vec<String> = Vec::new();
 
-    // items collected: (identifier, identifierref, parameters, org_id)
-    let mut items: Vec<(String, String, Option<String>, Option<String>)> = Vec::new();
+    // items collected in document order
+    let mut items: Vec<ItemInfo> = Vec::new();
+    // open <item> elements; Some(index into items) when the item was collected
+    let mut item_stack: Vec<Option<usize>> = Vec::new();
 
     // track current resource id to attach <file> tags
     let mut current_res_id: Option<String> = None;
 
     // organizations/default selection
-    let mut in_organizations = false;
     let mut default_org_id: Option<String> = None;
     let mut current_org_id: Option<String> = None;
 
+    // SCORM version: first <schemaversion> wins, else namespace sniffing
+    let mut in_schemaversion = false;
+    let mut schemaversion: Option<ScormVersion> = None;
+    let mut ns_2004 = false;
+
+    // <thumbnail>/<icon> in metadata, either href="..." or text content
+    let mut in_thumbnail = false;
+    let mut thumbnail_href: Option<String> = None;
+
+    // <adlcp:masteryscore> text; the first parseable value wins
+    let mut in_masteryscore = false;
+    let mut mastery_score: Option<f64> = None;
+    // <adlcp:prerequisites> text, attached to the innermost open item
+    let mut in_prerequisites = false;
+    // <adlcp:datafromlms> (2004: dataFromLMS) text for the innermost open item, or
+    // for the organization when outside any item
+    let mut in_datafromlms = false;
+    let mut org_data_from_lms: HashMap<String, String> = HashMap::new();
+
+    // LOM <general> title/description/keyword under the manifest's own <metadata>;
+    // `lom_field_done` once the field's first (language) string is taken
+    let mut in_manifest_metadata = false;
+    let mut in_lom_general = false;
+    let mut lom_field: Option<LomField> = None;
+    let mut lom_field_done = false;
+    let mut lom = LomGeneral::default();
+
     // first item reference inside the selected default org
     let mut first_item_ref_in_default_org: Option<String> = None;
     // fallback: first item reference anywhere
     let mut first_item_ref_any: Option<String> = None;
+    // first item reference of every organization
+    let mut org_first_refs: Vec<(String, String)> = Vec::new();
 
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
                 let name = local_name(&e);
                 match name.as_str() {
+                    "manifest" => {
+                        // 2004 packages declare the adlcp_v1p3 namespace even without schemaversion
+                        ns_2004 = e
+                            .attributes()
+                            .flatten()
+                            .any(|a| a.value.as_ref().windows(10).any(|w| w == b"adlcp_v1p3"));
+                    }
+                    "schemaversion" => {
+                        in_schemaversion = true;
+                    }
+                    // item and resource <metadata> describe those, not the package
+                    "metadata"
+                        if item_stack.is_empty()
+                            && current_res_id.is_none()
+                            && current_org_id.is_none() =>
+                    {
+                        in_manifest_metadata = true;
+                    }
+                    "general" if in_manifest_metadata => {
+                        in_lom_general = true;
+                    }
+                    "title" if in_lom_general => {
+                        lom_field = Some(LomField::Title);
+                        lom_field_done = false;
+                    }
+                    "description" if in_lom_general => {
+                        lom_field = Some(LomField::Description);
+                        lom_field_done = false;
+                    }
+                    "keyword" if in_lom_general => {
+                        lom_field = Some(LomField::Keyword);
+                        lom_field_done = false;
+                    }
+                    "masteryscore" => {
+                        in_masteryscore = true;
+                    }
+                    "prerequisites" => {
+                        in_prerequisites = true;
+                    }
+                    n if n.eq_ignore_ascii_case("datafromlms") => {
+                        in_datafromlms = true;
+                    }
+                    "thumbnail" | "icon" => {
+                        in_thumbnail = true;
+                        if thumbnail_href.is_none() {
+                            thumbnail_href = get_attr(&e, "href");
+                        }
+                    }
                     "organizations" => {
-                        in_organizations = true;
                         // read default="orgid" if present
                         default_org_id = get_attr(&e, "default");
                     }
@@ -96,9 +644,15 @@ pub fn parse_manifest(path: &PathBuf) -> Result<ParsedManifest, MfErr> {
                         current_org_id = get_attr(&e, "identifier");
                     }
                     "item" => {
+                        if item_stack.len() >= MAX_ITEM_DEPTH {
+                            return Err(MfErr::TooDeep);
+                        }
+                        let mut collected = None;
                         let identifier = get_attr(&e, "identifier");
                         let identifierref = get_attr(&e, "identifierref");
                         let parameters = get_attr(&e, "parameters");
+                        let visible = get_attr(&e, "isvisible")
+                            .is_none_or(|v| !v.trim().eq_ignore_ascii_case("false"));
                         if let (Some(id), Some(iref)) = (identifier, identifierref.clone()) {
                             let org_for_item = current_org_id.clone();
                             if first_item_ref_any.is_none() {
@@ -114,82 +668,159 @@ pub fn parse_manifest(path: &PathBuf) -> Result<ParsedManifest, MfErr> {
                             if is_default_org && first_item_ref_in_default_org.is_none() {
                                 first_item_ref_in_default_org = Some(iref.clone());
                             }
-                            items.push((id, iref, parameters, org_for_item));
+                            if let Some(org) = org_for_item.clone() {
+                                if !org_first_refs.iter().any(|(o, _)| *o == org) {
+                                    org_first_refs.push((org, iref.clone()));
+                                }
+                            }
+                            collected = Some(items.len());
+                            items.push(ItemInfo {
+                                identifier: id,
+                                identifierref: iref,
+                                parameters,
+                                control_mode: ControlMode::default(),
+                                visible,
+                                prerequisites: None,
+                                organization: org_for_item.clone(),
+                                data_from_lms: None,
+                            });
                         }
+                        item_stack.push(collected);
+                    }
+                    "controlMode" => {
+                        apply_control_mode(&e, &item_stack, &mut items);
                     }
                     "resource" => {
                         // Handle non-empty <resource> ... </resource>
-                        if let Some(id) = get_attr(&e, "identifier") {
-                            let mut info = resources.remove(&id).unwrap_or_default();
-                            if let Some(h) = get_attr(&e, "href") {
-                                info.href = Some(h);
-                            }
-                            if let Some(st) = get_attr(&e, "scormtype")
-                                .or_else(|| get_ns_attr(&e, "adlcp", "scormtype"))
-                            {
-                                info.scormtype = Some(st);
-                            }
-                            resources.insert(id.clone(), info);
-                            current_res_id = Some(id);
-                        }
+                        current_res_id =
+                            Some(record_resource(&e, &mut resources, &mut resource_order));
                     }
                     "file" => {
                         // Some manifests use <file href="..."/>; sometimes Start+End, be permissive
                         if let (Some(res_id), Some(href)) =
                             (current_res_id.clone(), get_attr(&e, "href"))
                         {
-                            resources
-                                .entry(res_id)
-                                .or_default()
-                                .files
-                                .push(href);
+                            resources.entry(res_id).or_default().files.push(href);
                         }
                     }
+                    "dependency" => {
+                        record_dependency(&e, current_res_id.as_deref(), &mut resources);
+                    }
                     _ => {}
                 }
             }
             Ok(Event::Empty(e)) => {
                 let name = local_name(&e);
                 match name.as_str() {
+                    "controlMode" => {
+                        apply_control_mode(&e, &item_stack, &mut items);
+                    }
+                    "thumbnail" | "icon" if thumbnail_href.is_none() => {
+                        thumbnail_href = get_attr(&e, "href");
+                    }
                     "resource" => {
                         // Handle <resource .../> (self-closing)
-                        if let Some(id) = get_attr(&e, "identifier") {
-                            let mut info = resources.remove(&id).unwrap_or_default();
-                            if let Some(h) = get_attr(&e, "href") {
-                                info.href = Some(h);
-                            }
-                            if let Some(st) = get_attr(&e, "scormtype")
-                                .or_else(|| get_ns_attr(&e, "adlcp", "scormtype"))
-                            {
-                                info.scormtype = Some(st);
-                            }
-                            resources.insert(id, info);
-                        }
+                        record_resource(&e, &mut resources, &mut resource_order);
                     }
                     "file" => {
                         // <file href="..."/> inside a <resource>
                         if let (Some(res_id), Some(href)) =
                             (current_res_id.clone(), get_attr(&e, "href"))
                         {
-                            resources
-                                .entry(res_id)
-                                .or_default()
-                                .files
-                                .push(href);
+                            resources.entry(res_id).or_default().files.push(href);
                         }
                     }
+                    "dependency" => {
+                        // <dependency identifierref="..."/> inside a <resource>
+                        record_dependency(&e, current_res_id.as_deref(), &mut resources);
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Text(t)) if in_schemaversion && schemaversion.is_none() => {
+                let v = t.unescape().map_err(|_| MfErr::Parse)?;
+                schemaversion = Some(ScormVersion::from_schemaversion(v.trim()));
+            }
+            Ok(Event::Text(t)) if in_masteryscore && mastery_score.is_none() => {
+                let v = t.unescape().map_err(|_| MfErr::Parse)?;
+                mastery_score = v
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|m| (0.0..=100.0).contains(m));
+            }
+            Ok(Event::Text(t)) if in_prerequisites => {
+                let v = t.unescape().map_err(|_| MfErr::Parse)?;
+                if let Some(Some(idx)) = item_stack.last() {
+                    let v = v.trim();
+                    items[*idx].prerequisites = (!v.is_empty()).then(|| v.to_string());
+                }
+            }
+            Ok(Event::Text(t)) if in_datafromlms => {
+                let v = t.unescape().map_err(|_| MfErr::Parse)?;
+                match (item_stack.last(), &current_org_id) {
+                    (Some(Some(idx)), _) => items[*idx].data_from_lms = Some(v.into_owned()),
+                    (None, Some(org)) => {
+                        org_data_from_lms.insert(org.clone(), v.into_owned());
+                    }
+                    // inside an item without a resource, or outside organizations
                     _ => {}
                 }
             }
+            Ok(Event::Text(t)) if !lom_field_done && lom_field.is_some() => {
+                let v = t.unescape().map_err(|_| MfErr::Parse)?;
+                let v = v.trim().to_string();
+                if !v.is_empty() {
+                    match lom_field {
+                        Some(LomField::Title) => lom.title = lom.title.or(Some(v)),
+                        Some(LomField::Description) => {
+                            lom.description = lom.description.or(Some(v))
+                        }
+                        Some(LomField::Keyword) | None => lom.keywords.push(v),
+                    }
+                    lom_field_done = true;
+                }
+            }
+            Ok(Event::Text(t)) if in_thumbnail && thumbnail_href.is_none() => {
+                let v = t.unescape().map_err(|_| MfErr::Parse)?;
+                let v = v.trim();
+                if !v.is_empty() {
+                    thumbnail_href = Some(v.to_string());
+                }
+            }
             Ok(Event::End(e)) => {
                 let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                let name = name.split(':').last().unwrap_or(&name);
+                let name = name.rsplit(':').next().unwrap_or(&name);
                 match name {
+                    "schemaversion" => {
+                        in_schemaversion = false;
+                    }
+                    "metadata" => {
+                        in_manifest_metadata = false;
+                    }
+                    "general" => {
+                        in_lom_general = false;
+                    }
+                    "title" | "description" | "keyword" => {
+                        lom_field = None;
+                    }
+                    "masteryscore" => {
+                        in_masteryscore = false;
+                    }
+                    "prerequisites" => {
+                        in_prerequisites = false;
+                    }
+                    n if n.eq_ignore_ascii_case("datafromlms") => {
+                        in_datafromlms = false;
+                    }
+                    "thumbnail" | "icon" => {
+                        in_thumbnail = false;
+                    }
                     "organization" => {
                         current_org_id = None;
                     }
-                    "organizations" => {
-                        in_organizations = false;
+                    "item" => {
+                        item_stack.pop();
                     }
                     "resource" => {
                         current_res_id = None;
@@ -209,38 +840,226 @@ pub fn parse_manifest(path: &PathBuf) -> Result<ParsedManifest, MfErr> {
         .or(first_item_ref_any)
         .or_else(|| {
             // As a last resort: pick the first resource with a usable href
-            first_resource_href(&resources)
+            first_resource_href(&resources, &resource_order)
         })
         .ok_or(MfErr::Parse)?;
 
     // Resolve an href for that resource
     let default_launch = resolve_launch_href(&resources, &chosen_item_ref)
-        .or_else(|| first_resource_href(&resources))
+        .or_else(|| first_resource_href(&resources, &resource_order))
         .ok_or(MfErr::Parse)?;
 
+    let organizations = org_first_refs
+        .into_iter()
+        .filter_map(|(org, iref)| Some((org, resolve_launch_href(&resources, &iref)?)))
+        .collect();
+
     // Build the SCOs list
-    let scos = items
+    let root = path.parent();
+    let mut warnings = Vec::new();
+    let scos: Vec<ParsedSco> = items
         .into_iter()
-        .filter_map(|(ident, identifierref, params, _org)| {
-            resolve_launch_href(&resources, &identifierref)
-                .map(|href| (ident, href, params))
+        .filter_map(|item| {
+            let launch_data = item.data_from_lms.or_else(|| {
+                item.organization
+                    .as_ref()
+                    .and_then(|org| org_data_from_lms.get(org))
+                    .cloned()
+            });
+            let Some(href) = resolve_launch_href(&resources, &item.identifierref) else {
+                warnings.push(format!(
+                    "item {} skipped: resource {} is missing or has no href",
+                    item.identifier, item.identifierref
+                ));
+                return None;
+            };
+            Some(ParsedSco {
+                identifier: item.identifier,
+                launch_data,
+                resource: Some(item.identifierref),
+                mobile_href: root.and_then(|dir| mobile_variant(dir, &href)),
+                href,
+                parameters: item.parameters,
+                control_mode: item.control_mode,
+                visible: item.visible,
+                prerequisites: item.prerequisites,
+            })
         })
         .collect();
+    for id in &resource_order {
+        for dep in resources.get(id).map_or(&[][..], |r| &r.dependencies[..]) {
+            if !resources.contains_key(dep) {
+                warnings.push(format!(
+                    "resource {} depends on unknown resource {}",
+                    id, dep
+                ));
+            }
+        }
+    }
 
-    Ok(ParsedManifest { default_launch, scos })
+    let mut launch_candidates = vec![default_launch.clone()];
+    for href in scos
+        .first()
+        .map(|sco| sco.href.clone())
+        .into_iter()
+        .chain(first_resource_href(&resources, &resource_order))
+    {
+        if !launch_candidates.contains(&href) {
+            launch_candidates.push(href);
+        }
+    }
+
+    // fall back to a conventional poster/thumbnail next to the manifest
+    let thumbnail_href = thumbnail_href.or_else(|| well_known_thumbnail(path.parent()?));
+
+    Ok(ParsedManifest {
+        default_launch,
+        launch_candidates,
+        scos,
+        thumbnail_href,
+        organizations,
+        org_identifier: None,
+        mastery_score,
+        resources: expand_resources(&resources, &resource_order),
+        warnings,
+        lom,
+        scorm_version: schemaversion.unwrap_or(if ns_2004 {
+            ScormVersion::Scorm2004
+        } else {
+            ScormVersion::Scorm12
+        }),
+    })
+}
+
+/// First candidate href whose file exists under `course_dir` (query/fragment ignored).
+pub fn first_existing_href(course_dir: &Path, candidates: &[String]) -> Option<String> {
+    candidates
+        .iter()
+        .find(|href| {
+            let file = href.split(['?', '#']).next().unwrap_or(href);
+            course_dir.join(file).is_file()
+        })
+        .cloned()
+}
+
+/// Mobile variant of a launch href by naming convention: `lesson/index.html`
+/// pairs with `lesson/index.mobile.html`. Query and fragment carry over.
+pub fn mobile_variant(course_dir: &Path, href: &str) -> Option<String> {
+    let split = href.find(['?', '#']).unwrap_or(href.len());
+    let (file, rest) = href.split_at(split);
+    let name_start = file.rfind('/').map_or(0, |i| i + 1);
+    let dot = file[name_start..].rfind('.').map(|i| name_start + i)?;
+    let candidate = format!("{}.mobile{}", &file[..dot], &file[dot..]);
+    course_dir
+        .join(&candidate)
+        .is_file()
+        .then(|| format!("{}{}", candidate, rest))
+}
+
+/// Package-relative files the manifest references (resource `<file>`s and SCO
+/// launch hrefs) that are not under `dir`; absolute URLs are not checked.
+pub fn verify_resources(dir: &Path, parsed: &ParsedManifest) -> Vec<String> {
+    let referenced = parsed
+        .resources
+        .iter()
+        .flat_map(|r| r.files.iter())
+        .chain(parsed.scos.iter().map(|s| &s.href));
+    let mut missing: Vec<String> = Vec::new();
+    for href in referenced {
+        let file = href.split(['?', '#']).next().unwrap_or(href);
+        if file.is_empty() || util::is_absolute_url(file) || missing.iter().any(|m| m == file) {
+            continue;
+        }
+        // hrefs are URLs: `my%20page.html` names `my page.html`
+        let decoded = percent_encoding::percent_decode_str(file).decode_utf8_lossy();
+        if !dir.join(file).is_file() && !dir.join(decoded.as_ref()).is_file() {
+            missing.push(file.to_string());
+        }
+    }
+    missing
+}
+
+/// Launch file must exist and be non-empty; the error names the problem.
+pub fn verify_launch_file(course_dir: &Path, href: &str) -> Result<(), String> {
+    let file = href.split(['?', '#']).next().unwrap_or(href);
+    match fs::metadata(course_dir.join(file)) {
+        Ok(m) if m.is_file() && m.len() > 0 => Ok(()),
+        Ok(m) if m.is_file() => Err(format!("launch file is empty: {}", file)),
+        _ => Err(format!("launch file not found in package: {}", file)),
+    }
+}
+
+const WELL_KNOWN_THUMBNAILS: &[&str] =
+    &["poster.jpg", "poster.png", "thumbnail.jpg", "thumbnail.png"];
+
+fn well_known_thumbnail(dir: &Path) -> Option<String> {
+    WELL_KNOWN_THUMBNAILS
+        .iter()
+        .find(|f| dir.join(f).is_file())
+        .map(|f| f.to_string())
+}
+
+/// Synthetic single-SCO manifest for plain HTML packages without `imsmanifest.xml`:
+/// launches a root `index.html`/`index.htm`, else the package's only HTML file.
+pub fn fallback_manifest(dir: &Path) -> Option<ParsedManifest> {
+    let href = ["index.html", "index.htm"]
+        .iter()
+        .find(|f| dir.join(f).is_file())
+        .map(|f| f.to_string())
+        .or_else(|| {
+            let mut html = WalkDir::new(dir)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    e.file_type().is_file()
+                        && e.path().extension().is_some_and(|x| {
+                            x.eq_ignore_ascii_case("html") || x.eq_ignore_ascii_case("htm")
+                        })
+                });
+            let only = html.next()?;
+            if html.next().is_some() {
+                return None;
+            }
+            let rel = only.path().strip_prefix(dir).ok()?;
+            Some(rel.to_string_lossy().replace('\\', "/"))
+        })?;
+
+    Some(ParsedManifest {
+        default_launch: href.clone(),
+        launch_candidates: vec![href.clone()],
+        scos: vec![ParsedSco {
+            identifier: "SCO-1".into(),
+            href,
+            parameters: None,
+            control_mode: ControlMode::default(),
+            visible: true,
+            prerequisites: None,
+            resource: None,
+            mobile_href: None,
+            launch_data: None,
+        }],
+        scorm_version: ScormVersion::Scorm12,
+        thumbnail_href: well_known_thumbnail(dir),
+        organizations: Vec::new(),
+        org_identifier: None,
+        mastery_score: None,
+        resources: Vec::new(),
+        warnings: Vec::new(),
+        lom: LomGeneral::default(),
+    })
 }
 
 // ------------- helpers -------------
 
 fn local_name(tag: &BytesStart<'_>) -> String {
     let full = String::from_utf8_lossy(tag.name().as_ref()).to_string();
-    full.split(':').last().unwrap_or(&full).to_string()
+    full.rsplit(':').next().unwrap_or(&full).to_string()
 }
 
 fn get_attr(e: &BytesStart<'_>, key_local: &str) -> Option<String> {
     for a in e.attributes().flatten() {
         let key = std::str::from_utf8(a.key.as_ref()).unwrap_or_default();
-        let key = key.split(':').last().unwrap_or(key);
+        let key = key.rsplit(':').next().unwrap_or(key);
         if key == key_local {
             return Some(a.unescape_value().ok()?.into_owned());
         }
@@ -260,7 +1079,26 @@ fn get_ns_attr(e: &BytesStart<'_>, ns: &str, key: &str) -> Option<String> {
     None
 }
 
-fn resolve_launch_href(resources: &HashMap<String, ResourceInfo>, identifierref: &str) -> Option<String> {
+// <imsss:controlMode> applies to the innermost open <item>
+fn apply_control_mode(e: &BytesStart<'_>, item_stack: &[Option<usize>], items: &mut [ItemInfo]) {
+    let Some(Some(idx)) = item_stack.last() else {
+        return;
+    };
+    let mode = &mut items[*idx].control_mode;
+    let flag = |key: &str, default: bool| {
+        get_attr(e, key)
+            .map(|v| v.trim() == "true")
+            .unwrap_or(default)
+    };
+    mode.choice = flag("choice", mode.choice);
+    mode.flow = flag("flow", mode.flow);
+    mode.forward_only = flag("forwardOnly", mode.forward_only);
+}
+
+fn resolve_launch_href(
+    resources: &HashMap<String, ResourceInfo>,
+    identifierref: &str,
+) -> Option<String> {
     let r = resources.get(identifierref)?;
     if let Some(h) = &r.href {
         return Some(h.clone());
@@ -269,8 +1107,86 @@ fn resolve_launch_href(resources: &HashMap<String, ResourceInfo>, identifierref:
     r.files.first().cloned()
 }
 
-fn first_resource_href(resources: &HashMap<String, ResourceInfo>) -> Option<String> {
-    for (_id, r) in resources.iter() {
+/// Record a `<resource>` and return its identifier. Resources without one get a
+/// synthetic `__resource_<n>` (n = document position) so they stay launchable.
+fn record_resource(
+    e: &BytesStart,
+    resources: &mut HashMap<String, ResourceInfo>,
+    order: &mut Vec<String>,
+) -> String {
+    let id = get_attr(e, "identifier").unwrap_or_else(|| format!("__resource_{}", order.len()));
+    if !order.contains(&id) {
+        order.push(id.clone());
+    }
+    let info = resources.entry(id.clone()).or_default();
+    if let Some(h) = get_attr(e, "href") {
+        info.href = Some(h);
+    }
+    if let Some(st) = get_attr(e, "scormtype").or_else(|| get_ns_attr(e, "adlcp", "scormtype")) {
+        info.scormtype = Some(st);
+    }
+    id
+}
+
+fn record_dependency(
+    e: &BytesStart,
+    current_res_id: Option<&str>,
+    resources: &mut HashMap<String, ResourceInfo>,
+) {
+    if let (Some(res_id), Some(dep)) = (current_res_id, get_attr(e, "identifierref")) {
+        resources
+            .entry(res_id.to_string())
+            .or_default()
+            .dependencies
+            .push(dep);
+    }
+}
+
+/// Resources in document order, each with the files of its dependency closure.
+fn expand_resources(
+    resources: &HashMap<String, ResourceInfo>,
+    order: &[String],
+) -> Vec<ParsedResource> {
+    order
+        .iter()
+        .filter_map(|id| {
+            let info = resources.get(id)?;
+            let mut files: Vec<String> = Vec::new();
+            let mut seen = vec![id.as_str()];
+            let mut queue = vec![id.as_str()];
+            // breadth-first so a resource's own files come before its dependencies'
+            while !queue.is_empty() {
+                let mut next = Vec::new();
+                for r in queue.iter().filter_map(|r| resources.get(*r)) {
+                    for f in &r.files {
+                        if !files.contains(f) {
+                            files.push(f.clone());
+                        }
+                    }
+                    for dep in &r.dependencies {
+                        if !seen.contains(&dep.as_str()) {
+                            seen.push(dep);
+                            next.push(dep.as_str());
+                        }
+                    }
+                }
+                queue = next;
+            }
+            Some(ParsedResource {
+                identifier: id.clone(),
+                href: info.href.clone(),
+                files,
+            })
+        })
+        .collect()
+}
+
+/// First resource in document order with a usable href.
+fn first_resource_href(
+    resources: &HashMap<String, ResourceInfo>,
+    order: &[String],
+) -> Option<String> {
+    for r in order.iter().filter_map(|id| resources.get(id)) {
         if let Some(h) = &r.href {
             return Some(h.clone());
         }
@@ -281,3 +1197,380 @@ fn first_resource_href(resources: &HashMap<String, ResourceInfo>) -> Option<Stri
     None
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh temp dir holding `imsmanifest.xml` with `xml` plus empty `files`.
+    fn package_dir(xml: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("scorm-mf-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("imsmanifest.xml"), xml).unwrap();
+        for f in files {
+            let path = dir.join(f);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+        dir
+    }
+
+    fn parse(dir: &Path) -> ParsedManifest {
+        parse_manifest(&dir.join("imsmanifest.xml"), 1 << 20).unwrap()
+    }
+
+    #[test]
+    fn missing_default_launch_falls_back_to_first_resource() {
+        let dir = package_dir(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+              <organizations default="o"><organization identifier="o">
+                <item identifier="i1" identifierref="r1"><title>Main</title></item>
+              </organization></organizations>
+              <resources>
+                <resource identifier="r0" type="webcontent" adlcp:scormtype="asset" href="intro.html"/>
+                <resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="main.html"/>
+              </resources>
+            </manifest>"#,
+            &["intro.html"],
+        );
+        let parsed = parse(&dir);
+        assert_eq!(parsed.launch_candidates, ["main.html", "intro.html"]);
+        assert_eq!(
+            first_existing_href(&dir, &parsed.launch_candidates).as_deref(),
+            Some("intro.html")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sequencing_control_mode_applies_to_its_item() {
+        let dir = package_dir(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_v1p3"
+                         xmlns:imsss="http://www.imsglobal.org/xsd/imsss">
+              <organizations default="o"><organization identifier="o">
+                <item identifier="i1" identifierref="r1"><title>Locked</title>
+                  <imsss:sequencing>
+                    <imsss:controlMode choice="false" flow="true" forwardOnly="true"/>
+                  </imsss:sequencing>
+                </item>
+                <item identifier="i2" identifierref="r2"><title>Default</title></item>
+              </organization></organizations>
+              <resources>
+                <resource identifier="r1" type="webcontent" adlcp:scormType="sco" href="a.html"/>
+                <resource identifier="r2" type="webcontent" adlcp:scormType="sco" href="b.html"/>
+              </resources>
+            </manifest>"#,
+            &[],
+        );
+        let parsed = parse(&dir);
+        assert_eq!(
+            parsed.scos[0].control_mode,
+            ControlMode {
+                choice: false,
+                flow: true,
+                forward_only: true,
+            }
+        );
+        assert_eq!(parsed.scos[1].control_mode, ControlMode::default());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn identifier_less_resource_still_provides_a_default_launch() {
+        let dir = package_dir(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+              <organizations/>
+              <resources>
+                <resource type="webcontent" adlcp:scormtype="sco" href="start.html"/>
+              </resources>
+            </manifest>"#,
+            &["start.html"],
+        );
+        let parsed = parse(&dir);
+        assert_eq!(parsed.resources[0].identifier, "__resource_0");
+        assert_eq!(parsed.launch_candidates, ["start.html"]);
+        assert_eq!(
+            first_existing_href(&dir, &parsed.launch_candidates).as_deref(),
+            Some("start.html")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn oversized_or_too_deep_manifest_is_rejected() {
+        let dir = package_dir(&format!("<manifest>{}</manifest>", " ".repeat(2048)), &[]);
+        let path = dir.join("imsmanifest.xml");
+        assert!(matches!(
+            parse_manifest(&path, 1024),
+            Err(MfErr::TooLarge(1024))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let depth = MAX_ITEM_DEPTH + 1;
+        let dir = package_dir(
+            &format!(
+                r#"<manifest><organizations><organization identifier="o">{}{}</organization></organizations></manifest>"#,
+                r#"<item identifier="i">"#.repeat(depth),
+                "</item>".repeat(depth)
+            ),
+            &[],
+        );
+        assert!(matches!(
+            parse_manifest(&dir.join("imsmanifest.xml"), 1 << 20),
+            Err(MfErr::TooDeep)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn legacy_html_is_transcoded_to_utf8() {
+        let dir = package_dir("<manifest/>", &[]);
+        // "Café – ½" in windows-1252, with a declared legacy charset
+        let legacy = b"<html><head><meta charset=\"windows-1252\"></head><body>Caf\xe9 \x96 \xbd</body></html>";
+        fs::write(dir.join("page.html"), legacy).unwrap();
+        fs::write(dir.join("plain.html"), b"<p>Caf\xe9</p>").unwrap();
+        fs::write(dir.join("ok.html"), "<p>Café</p>").unwrap();
+
+        assert_eq!(transcode_legacy_html(&dir).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(dir.join("page.html")).unwrap(),
+            "<html><head><meta charset=\"utf-8\"></head><body>Café – ½</body></html>"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("plain.html")).unwrap(),
+            "<meta charset=\"utf-8\"><p>Café</p>"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("ok.html")).unwrap(),
+            "<p>Café</p>"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn extract_opts(symlinks: SymlinkPolicy) -> ExtractOptions {
+        ExtractOptions {
+            symlinks,
+            case_collisions: CaseCollisionPolicy::Warn,
+            overwrite: false,
+            max_path_depth: 32,
+            max_path_len: 4096,
+        }
+    }
+
+    /// Zip holding `index.html` plus a symlink entry `escape` -> `/etc/passwd`.
+    fn zip_with_symlink() -> Vec<u8> {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let opts = zip::write::FileOptions::default();
+        zip.start_file("index.html", opts).unwrap();
+        zip.write_all(b"<html></html>").unwrap();
+        zip.add_symlink("escape", "/etc/passwd", opts).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn symlink_entries_are_never_materialized() {
+        let bytes = zip_with_symlink();
+        let dir = std::env::temp_dir().join(format!("scorm-zip-{}", uuid::Uuid::new_v4()));
+
+        let files = extract_zip_to_dir(&bytes, &dir, extract_opts(SymlinkPolicy::Skip)).unwrap();
+        assert_eq!(files, 1);
+        assert!(dir.join("index.html").is_file());
+        assert!(fs::symlink_metadata(dir.join("escape")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let err =
+            extract_zip_to_dir(&bytes, &dir, extract_opts(SymlinkPolicy::Reject)).unwrap_err();
+        assert!(matches!(err, ExtractErr::Symlink(name) if name == "escape"));
+        assert!(fs::symlink_metadata(dir.join("escape")).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn entries_leaving_the_target_are_rejected_before_writing() {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let opts = zip::write::FileOptions::default();
+        zip.start_file("index.html", opts).unwrap();
+        zip.write_all(b"ok").unwrap();
+        zip.start_file("../evil.html", opts).unwrap();
+        zip.write_all(b"evil").unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+        let dir = std::env::temp_dir().join(format!("scorm-zip-{}", uuid::Uuid::new_v4()));
+
+        let err = extract_zip_to_dir(&bytes, &dir, extract_opts(SymlinkPolicy::Skip)).unwrap_err();
+        assert!(matches!(err, ExtractErr::UnsafePath(name) if name == "../evil.html"));
+        assert!(!dir.exists());
+        assert!(!dir.parent().unwrap().join("evil.html").exists());
+    }
+
+    #[test]
+    fn items_inherit_organization_datafromlms() {
+        let dir = package_dir(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+              <organizations default="o"><organization identifier="o">
+                <title>Course</title>
+                <adlcp:datafromlms>lang=en</adlcp:datafromlms>
+                <item identifier="i1" identifierref="r1"><title>Inherits</title></item>
+                <item identifier="i2" identifierref="r2">
+                  <title>Own</title>
+                  <adlcp:datafromlms>lang=fr</adlcp:datafromlms>
+                </item>
+              </organization></organizations>
+              <resources>
+                <resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="a.html"/>
+                <resource identifier="r2" type="webcontent" adlcp:scormtype="sco" href="b.html"/>
+              </resources>
+            </manifest>"#,
+            &[],
+        );
+        let parsed = parse(&dir);
+        let data: Vec<_> = parsed
+            .scos
+            .iter()
+            .map(|s| (s.identifier.as_str(), s.launch_data.as_deref()))
+            .collect();
+        assert_eq!(data, [("i1", Some("lang=en")), ("i2", Some("lang=fr"))]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn second_extraction_into_a_non_empty_dir_is_refused() {
+        use std::io::Write;
+        let zip_with = |contents: &[u8]| {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            zip.start_file("index.html", zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(contents).unwrap();
+            zip.finish().unwrap().into_inner()
+        };
+        let dir = std::env::temp_dir().join(format!("scorm-zip-{}", uuid::Uuid::new_v4()));
+        let opts = extract_opts(SymlinkPolicy::Skip);
+
+        assert_eq!(
+            extract_zip_to_dir(&zip_with(b"first"), &dir, opts).unwrap(),
+            1
+        );
+        let err = extract_zip_to_dir(&zip_with(b"second"), &dir, opts).unwrap_err();
+        assert!(matches!(&err, ExtractErr::TargetNotEmpty(p) if *p == dir));
+        assert_eq!(fs::read(dir.join("index.html")).unwrap(), b"first");
+
+        let overwrite = ExtractOptions {
+            overwrite: true,
+            ..opts
+        };
+        extract_zip_to_dir(&zip_with(b"second"), &dir, overwrite).unwrap();
+        assert_eq!(fs::read(dir.join("index.html")).unwrap(), b"second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lom_general_fields_are_captured() {
+        let lom_12 = r#"<metadata><schema>ADL SCORM</schema><schemaversion>1.2</schemaversion>
+              <imsmd:lom><imsmd:general>
+                <imsmd:title><imsmd:langstring xml:lang="en">Fire Safety</imsmd:langstring></imsmd:title>
+                <imsmd:description>
+                  <imsmd:langstring xml:lang="en">Evacuation basics</imsmd:langstring>
+                  <imsmd:langstring xml:lang="fr">Bases de l'évacuation</imsmd:langstring>
+                </imsmd:description>
+                <imsmd:keyword><imsmd:langstring>fire</imsmd:langstring></imsmd:keyword>
+                <imsmd:keyword><imsmd:langstring>safety</imsmd:langstring></imsmd:keyword>
+              </imsmd:general></imsmd:lom></metadata>"#;
+        let lom_10 = r#"<metadata><lom xmlns="http://ltsc.ieee.org/xsd/LOM"><general>
+                <title><string language="en">Fire Safety</string></title>
+                <description><string language="en">Evacuation basics</string></description>
+                <keyword><string language="en">fire</string></keyword>
+                <keyword><string language="en">safety</string></keyword>
+              </general></lom></metadata>"#;
+        for metadata in [lom_12, lom_10] {
+            let dir = package_dir(
+                &format!(
+                    r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2"
+                        xmlns:imsmd="http://www.imsglobal.org/xsd/imsmd_rootv1p2p1">
+                      {metadata}
+                      <organizations default="o"><organization identifier="o">
+                        <title>Org title</title>
+                        <item identifier="i1" identifierref="r1"><title>Main</title>
+                          <metadata><lom><general><description><string>item only</string></description>
+                            <keyword><string>ignored</string></keyword></general></lom></metadata>
+                        </item>
+                      </organization></organizations>
+                      <resources>
+                        <resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="a.html"/>
+                      </resources>
+                    </manifest>"#
+                ),
+                &[],
+            );
+            let lom = parse(&dir).lom;
+            assert_eq!(lom.title.as_deref(), Some("Fire Safety"));
+            assert_eq!(lom.description.as_deref(), Some("Evacuation basics"));
+            assert_eq!(lom.keywords, ["fire", "safety"]);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn entries_differing_only_in_case_warn_or_reject() {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let opts = zip::write::FileOptions::default();
+        for name in ["img/Logo.png", "img/logo.png"] {
+            zip.start_file(name, opts).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        let dir = std::env::temp_dir().join(format!("scorm-zip-{}", uuid::Uuid::new_v4()));
+
+        let warn = extract_opts(SymlinkPolicy::Skip);
+        assert_eq!(extract_zip_to_dir(&bytes, &dir, warn).unwrap(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let reject = ExtractOptions {
+            case_collisions: CaseCollisionPolicy::Reject,
+            ..warn
+        };
+        let err = extract_zip_to_dir(&bytes, &dir, reject).unwrap_err();
+        assert!(err.is_unsafe_entry());
+        assert!(matches!(&err, ExtractErr::CaseCollision(first, second)
+                if first == "img/Logo.png" && second == "img/logo.png"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn over_long_or_deep_entries_are_rejected_before_writing() {
+        use std::io::Write;
+        let zip_with = |name: &str| {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            zip.start_file("index.html", zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(b"ok").unwrap();
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(b"x").unwrap();
+            zip.finish().unwrap().into_inner()
+        };
+        let dir = std::env::temp_dir().join(format!("scorm-zip-{}", uuid::Uuid::new_v4()));
+        let opts = ExtractOptions {
+            max_path_depth: 3,
+            max_path_len: dir.as_os_str().len() + 40,
+            ..extract_opts(SymlinkPolicy::Skip)
+        };
+
+        let deep = "a/b/c/d.html";
+        let err = extract_zip_to_dir(&zip_with(deep), &dir, opts).unwrap_err();
+        assert!(err.is_unsafe_entry());
+        assert!(matches!(&err, ExtractErr::PathTooDeep(name, 3) if name == deep));
+        assert!(!dir.exists());
+
+        let long = format!("{}.html", "x".repeat(40));
+        let err = extract_zip_to_dir(&zip_with(&long), &dir, opts).unwrap_err();
+        assert!(matches!(&err, ExtractErr::PathTooLong(name, _) if *name == long));
+        assert!(!dir.exists());
+
+        assert_eq!(
+            extract_zip_to_dir(&zip_with("a/b/c.html"), &dir, opts).unwrap(),
+            2
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
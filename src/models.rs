@@ -10,6 +10,109 @@ pub struct Course {
     pub launch_href: String,
     pub base_path: String, // relative to DATA_DIR, e.g. "courses/<uuid>"
     pub created_at: DateTime<Utc>,
+    pub launch_candidates: Vec<String>,
+    pub scorm_version: String, // "1.2" | "2004"
+    pub allowed_elements: Option<serde_json::Value>,
+    pub thumbnail_href: Option<String>,
+    pub min_session_seconds: Option<i32>,
+    pub mastery_score: Option<f64>,
+    pub max_attempts: Option<i32>,
+    // operator override; takes precedence over mastery_score when set
+    pub pass_threshold: Option<f64>,
+    pub default_launch_strategy: String,
+    // score-only SCOs: a raw score without lesson_status completes the attempt
+    pub complete_on_score: bool,
+    // from the manifest's LOM <general> metadata
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub launch_mode: String,
+}
+
+impl Course {
+    /// Score needed to pass: the operator's threshold, else the mastery score.
+    pub fn passing_score(&self) -> Option<f64> {
+        self.pass_threshold.or(self.mastery_score)
+    }
+
+    pub fn launch_strategy(&self) -> LaunchStrategy {
+        self.default_launch_strategy.parse().unwrap_or_default()
+    }
+
+    pub fn launch_mode(&self) -> LaunchMode {
+        self.launch_mode.parse().unwrap_or_default()
+    }
+}
+
+/// How the player picks a SCO for an attempt without `sco_id`
+/// (`courses.default_launch_strategy`, enforced by a check constraint).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchStrategy {
+    /// The manifest's default item (first existing launch candidate).
+    #[default]
+    ManifestDefault,
+    /// The first visible SCO in manifest order.
+    FirstSco,
+    /// The SCO of the registration's most recent other attempt.
+    Resume,
+}
+
+impl LaunchStrategy {
+    pub const ALL: [LaunchStrategy; 3] = [
+        LaunchStrategy::ManifestDefault,
+        LaunchStrategy::FirstSco,
+        LaunchStrategy::Resume,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LaunchStrategy::ManifestDefault => "manifest_default",
+            LaunchStrategy::FirstSco => "first_sco",
+            LaunchStrategy::Resume => "resume",
+        }
+    }
+}
+
+impl std::str::FromStr for LaunchStrategy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        LaunchStrategy::ALL
+            .into_iter()
+            .find(|st| st.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// Where the player runs the SCO (`courses.launch_mode`, enforced by a check constraint).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchMode {
+    /// In the player page's iframe.
+    #[default]
+    Frame,
+    /// Top-level in a window opened by the player, for frame-busting content.
+    Popup,
+}
+
+impl LaunchMode {
+    pub const ALL: [LaunchMode; 2] = [LaunchMode::Frame, LaunchMode::Popup];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LaunchMode::Frame => "frame",
+            LaunchMode::Popup => "popup",
+        }
+    }
+}
+
+impl std::str::FromStr for LaunchMode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        LaunchMode::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or(())
+    }
 }
 
 #[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
@@ -20,6 +123,17 @@ pub struct Sco {
     pub launch_href: String,
     pub parameters: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub sort_order: i32,
+    // imsss controlMode (SCORM 2004); defaults apply to 1.2 packages
+    pub control_choice: bool,
+    pub control_flow: bool,
+    pub control_forward_only: bool,
+    // instructor feedback served read-only as cmi.comments_from_lms
+    pub comments_from_lms: Option<String>,
+    pub is_visible: bool,
+    pub prerequisites: Option<String>,
+    pub mobile_launch_href: Option<String>,
+    pub launch_data: Option<String>,
 }
 
 #[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
@@ -32,23 +146,302 @@ pub struct Attempt {
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub learner_name: Option<String>,
+    pub instructor_override: bool,
+    pub total_time_secs: f64,
+    pub registration_id: Uuid,
+    pub score_raw: Option<f64>,
+    pub score_min: Option<f64>,
+    pub score_max: Option<f64>,
+    pub score_scaled: Option<f64>,
+    pub random_seed: i64,
+    pub language: Option<String>,
+    pub return_url: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Every value `attempts.status` may hold (enforced by a check constraint);
+/// writes go through this enum rather than string literals.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptStatus {
+    InProgress,
+    Completed,
+    // finished with lesson_status `failed`
+    Failed,
+    Suspended,
+    TimedOut,
+    Expired,
+    LaunchError,
+}
+
+impl AttemptStatus {
+    pub const ALL: [AttemptStatus; 7] = [
+        AttemptStatus::InProgress,
+        AttemptStatus::Completed,
+        AttemptStatus::Failed,
+        AttemptStatus::Suspended,
+        AttemptStatus::TimedOut,
+        AttemptStatus::Expired,
+        AttemptStatus::LaunchError,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttemptStatus::InProgress => "in_progress",
+            AttemptStatus::Completed => "completed",
+            AttemptStatus::Failed => "failed",
+            AttemptStatus::Suspended => "suspended",
+            AttemptStatus::TimedOut => "timed_out",
+            AttemptStatus::Expired => "expired",
+            AttemptStatus::LaunchError => "launch_error",
+        }
+    }
+
+    /// Statuses a learner cannot continue from; `suspended` resumes.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            AttemptStatus::Completed
+                | AttemptStatus::Failed
+                | AttemptStatus::TimedOut
+                | AttemptStatus::Expired
+        )
+    }
+
+    /// Finished with the content done, passed or not; what completion rollups count.
+    pub fn is_completed(&self) -> bool {
+        matches!(self, AttemptStatus::Completed | AttemptStatus::Failed)
+    }
+}
+
+impl std::fmt::Display for AttemptStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for AttemptStatus {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        AttemptStatus::ALL
+            .into_iter()
+            .find(|st| st.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// One stored CMI element of an attempt.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct CmiValue {
+    pub attempt_id: Uuid,
+    pub element: String,
+    pub value: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    // value is `runtime::compress_value` output
+    pub compressed: bool,
+}
+
+/// A `cmi_value_log` row with the attempt's learner, as exported by events.jsonl.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValueLogEntry {
+    pub id: i64,
+    pub attempt_id: Uuid,
+    pub learner_id: String,
+    pub element: String,
+    pub value: Option<String>,
+    pub logged_at: DateTime<Utc>,
+}
+
+/// A learner's run through a course; groups the per-SCO attempts.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct Registration {
+    pub id: Uuid,
+    pub course_id: Uuid,
+    pub learner_id: String,
+    pub learner_name: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateAttemptReq {
     pub course_id: Uuid,
     pub learner_id: String,
+    pub learner_name: Option<String>,
+    pub sco_id: Option<Uuid>,
+    // deep link: pre-seeds cmi.core.lesson_location with entry=resume
+    pub start_location: Option<String>,
+    // launch under an existing registration (reusing its attempt for the same SCO)
+    pub registration_id: Option<Uuid>,
+    // learner's preferred language (e.g. "fr-CA"), seeded into the preference element
+    pub language: Option<String>,
+    // absolute http(s) URL echoed by finish for host redirects
+    pub return_url: Option<String>,
+    // caller-defined JSON object stored with the attempt and returned verbatim
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReassignAttemptReq {
+    pub learner_id: String,
+    pub learner_name: Option<String>,
+}
+
+/// Admin partial update; absent fields are left unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PatchAttemptReq {
+    pub status: Option<String>,
+    // "" clears the name
+    pub learner_name: Option<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BulkLearner {
+    pub learner_id: String,
+    pub learner_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BulkAttemptsReq {
+    pub course_id: Uuid,
+    pub learners: Vec<BulkLearner>,
     pub sco_id: Option<Uuid>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CourseTagsReq {
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchCommit {
+    pub attempt_id: Uuid,
+    #[serde(default)]
+    pub values: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommitBatchReq {
+    pub commits: Vec<BatchCommit>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadFromUrlReq {
+    pub title: Option<String>,
+    pub url: String,
+    pub organization_identifier: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportScoReq {
+    pub identifier: String,
+    pub launch_href: String,
+    pub parameters: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportCourseReq {
+    pub title: String,
+    pub base_path: String, // relative to DATA_DIR; files must already exist
+    pub launch_href: String,
+    #[serde(default)]
+    pub scos: Vec<ImportScoReq>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AllowedElementsReq {
+    // None clears the restriction
+    pub allowed_elements: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScoCommentsReq {
+    // None clears the comments
+    pub comments_from_lms: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MasteryScoreReq {
+    // None clears the mastery score
+    pub mastery_score: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LaunchStrategyReq {
+    pub default_launch_strategy: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LaunchModeReq {
+    pub launch_mode: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompleteOnScoreReq {
+    pub complete_on_score: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PassThresholdReq {
+    // None falls back to the mastery score
+    pub pass_threshold: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MaxAttemptsReq {
+    // None removes the cap
+    pub max_attempts: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MinSessionReq {
+    // None clears the minimum
+    pub min_session_seconds: Option<i32>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RuntimeSetReq {
     pub element: String,
     pub value: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetScoReq {
+    pub sco_id: Uuid,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RuntimeGetReq {
     pub element: String,
 }
 
+/// Published on the attempt event bus and streamed to `/api/attempts/:id/events`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttemptEvent {
+    pub attempt_id: Uuid,
+    pub kind: &'static str, // "values" | "status"
+    pub data: serde_json::Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attempt_status_round_trips_every_value() {
+        for status in AttemptStatus::ALL {
+            assert_eq!(status.as_str().parse::<AttemptStatus>(), Ok(status));
+            assert_eq!(status.to_string(), status.as_str());
+            let json = serde_json::to_value(status).unwrap();
+            assert_eq!(json, status.as_str());
+            assert_eq!(
+                serde_json::from_value::<AttemptStatus>(json).unwrap(),
+                status
+            );
+        }
+        for invalid in ["", "done", "Completed", "in-progress"] {
+            assert_eq!(invalid.parse::<AttemptStatus>(), Err(()), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Player page markup. The built-in template is src/player.html; operators can
+// swap in their own with `PLAYER_TEMPLATE`. Values go in through `{{name}}`
+// placeholders. Also the postMessage bridge for popup launches (src/player_bridge.js).
+
+pub const DEFAULT_TEMPLATE: &str = include_str!("player.html");
+
+/// Served at /player/bridge.js for cross-origin content in popup launch mode.
+pub const BRIDGE_JS: &str = include_str!("player_bridge.js");
+
+/// Replace each `{{name}}` in `template` with its value from `vars`. Unknown
+/// placeholders are kept verbatim, and substituted text is not scanned again.
+pub fn render(template: &str, vars: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len() + 1024);
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let known = after.find("}}").and_then(|end| {
+            vars.iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, v)| (end, v))
+        });
+        match known {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_fills_known_placeholders_only() {
+        let out = render(
+            "<b>{{title}}</b>{{unknown}}{{attempt_id}}",
+            &[
+                ("title", "{{attempt_id}}".to_string()),
+                ("attempt_id", "a1".to_string()),
+            ],
+        );
+        // substituted text is not rescanned; unknown names stay
+        assert_eq!(out, "<b>{{attempt_id}}</b>{{unknown}}a1");
+        assert_eq!(render("open {{ end", &[]), "open {{ end");
+    }
+}
@@ -0,0 +1,70 @@
+// SCORM 1.2 API for content that runs top-level in the player's popup
+// (launch_mode=popup) but on another origin, so it cannot reach window.opener.API.
+// Include it with <script src="https://<runtime host>/player/bridge.js"></script>.
+// Calls are relayed to the player with postMessage. LMSGetValue answers from the
+// snapshot the player puts in the launch URL's hash (kept in sessionStorage for
+// reloads), so reads work before any message has come back.
+(function(){
+  const opener = window.opener;
+  if (!opener || window.API) return;
+  const playerOrigin = new URL(document.currentScript.src).origin;
+  const cache = {};
+  const written = new Set();
+  let children = {};
+
+  const stored = ()=>{
+    try { sessionStorage.setItem('scorm_bridge', JSON.stringify({values:cache, children})); } catch(_){}
+  };
+  const seed = (snapshot)=>{
+    Object.assign(cache, snapshot.values || {});
+    children = snapshot.children || {};
+  };
+  const hashed = location.hash.match(/(^#|&)scorm_init=([^&]*)$/);
+  if (hashed) {
+    try { seed(JSON.parse(decodeURIComponent(hashed[2]))); stored(); } catch(_){}
+    // hand the content its own hash back
+    const rest = location.hash.slice(0, hashed.index);
+    history.replaceState(history.state, '', location.pathname + location.search + rest);
+  } else {
+    try { seed(JSON.parse(sessionStorage.getItem('scorm_bridge')) || {}); } catch(_){}
+  }
+
+  const send = (msg)=> opener.postMessage(Object.assign({scorm:1}, msg), playerOrigin);
+  const call = (method, args)=> send({type:'call', method, args});
+
+  window.addEventListener('message', (ev)=>{
+    if (ev.source !== opener || ev.origin !== playerOrigin) return;
+    const m = ev.data;
+    if (!m || m.scorm !== 1) return;
+    if (m.type === 'init') {
+      // the player's view may predate calls still in flight; local writes win
+      for (const [el, v] of Object.entries(m.values || {})) {
+        if (!written.has(el)) cache[el] = v;
+      }
+      children = m.children || children;
+      stored();
+    }
+  });
+
+  window.API = {
+    LMSInitialize(arg){ call('LMSInitialize', ['']); return "true"; },
+    LMSFinish(arg){ call('LMSFinish', ['']); return "true"; },
+    LMSGetValue(el){
+      if (el in children) return children[el];
+      return (el in cache) ? String(cache[el]) : "";
+    },
+    LMSSetValue(el, v){
+      cache[el] = String(v);
+      written.add(el);
+      stored();
+      call('LMSSetValue', [el, String(v)]);
+      return "true";
+    },
+    LMSCommit(arg){ call('LMSCommit', ['']); return "true"; },
+    LMSGetLastError(){ return "0"; },
+    LMSGetErrorString(c){ return "No error"; },
+    LMSGetDiagnostic(c){ return ""; }
+  };
+
+  send({type:'hello'});
+})();
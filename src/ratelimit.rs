@@ -0,0 +1,99 @@
+// Per-attempt token buckets for runtime writes (`RUNTIME_WRITES_PER_SECOND`),
+// so a SCO stuck in a commit loop cannot flood the database.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+pub struct WriteLimiter {
+    // refill rate; also the burst size
+    per_second: f64,
+    buckets: Mutex<HashMap<Uuid, Bucket>>,
+}
+
+impl WriteLimiter {
+    pub fn new(per_second: u32) -> Self {
+        WriteLimiter {
+            per_second: per_second as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one token from the attempt's bucket; false when it is empty.
+    pub fn allow(&self, attempt_id: Uuid) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("limiter lock poisoned");
+        let bucket = buckets.entry(attempt_id).or_insert(Bucket {
+            tokens: self.per_second,
+            refilled: now,
+        });
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.per_second);
+        bucket.refilled = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forget attempts without writes for `idle`; their buckets would be full again anyway.
+    pub fn sweep(&self, idle: Duration) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("limiter lock poisoned");
+        buckets.retain(|_, b| now.duration_since(b.refilled) < idle);
+    }
+}
+
+/// Sweep `limiter` once a minute in the background.
+pub fn spawn_sweeper(limiter: Arc<WriteLimiter>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            tick.tick().await;
+            limiter.sweep(Duration::from_secs(60));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills_per_attempt() {
+        let limiter = WriteLimiter::new(2);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert!(limiter.allow(a));
+        assert!(limiter.allow(a));
+        assert!(!limiter.allow(a));
+        // buckets are per attempt
+        assert!(limiter.allow(b));
+
+        std::thread::sleep(Duration::from_millis(600));
+        assert!(limiter.allow(a));
+        assert!(!limiter.allow(a));
+    }
+
+    #[test]
+    fn sweep_forgets_idle_attempts() {
+        let limiter = WriteLimiter::new(1);
+        let a = Uuid::new_v4();
+        assert!(limiter.allow(a));
+        assert!(!limiter.allow(a));
+        limiter.sweep(Duration::from_secs(60));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+        limiter.sweep(Duration::ZERO);
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+        assert!(limiter.allow(a));
+    }
+}
@@ -0,0 +1,920 @@
+// Repository seam for the stored state: courses, SCOs, registrations, attempts
+// and their CMI values. Handlers only go through these traits, so route logic
+// runs against `memory::MemRepo` in tests; retention and migrations use the pool.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_as};
+use std::pin::Pin;
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::{db::Db, manifest::ParsedManifest, models::*};
+
+#[cfg(test)]
+pub mod memory;
+
+/// One admin-editable course setting, for `CourseRepo::update_course`.
+#[derive(Debug, Clone)]
+pub enum CourseSetting {
+    AllowedElements(Option<serde_json::Value>),
+    MinSessionSeconds(Option<i32>),
+    MaxAttempts(Option<i32>),
+    MasteryScore(Option<f64>),
+    PassThreshold(Option<f64>),
+    LaunchStrategy(LaunchStrategy),
+    LaunchMode(LaunchMode),
+    CompleteOnScore(bool),
+}
+
+/// Admin attempt edit; `None` leaves a field unchanged, `Some("")` clears the name.
+#[derive(Debug, Clone, Default)]
+pub struct AttemptPatch {
+    pub status: Option<AttemptStatus>,
+    pub learner_name: Option<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Denormalized `cmi.core.score.*` columns of an attempt.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AttemptScores {
+    pub raw: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub scaled: Option<f64>,
+}
+
+/// What `AttemptRepo::rederive_course` writes back for one attempt.
+#[derive(Debug, Clone, Default)]
+pub struct Rederived {
+    // new cmi.core.lesson_status, when it changes
+    pub lesson_status: Option<String>,
+    // new attempt status, when it changes
+    pub status: Option<AttemptStatus>,
+}
+
+/// Maps an attempt and its stored values to the updates `rederive_course` applies.
+pub type Rederive = dyn Fn(&Attempt, &[CmiValue]) -> Rederived + Send + Sync;
+
+/// `cmi_value_log` rows, oldest first.
+pub type ValueLogStream = Pin<Box<dyn Stream<Item = Result<ValueLogEntry, sqlx::Error>> + Send>>;
+
+#[async_trait]
+pub trait CourseRepo: Send + Sync {
+    async fn course(&self, id: Uuid) -> Result<Option<Course>, sqlx::Error>;
+    /// All courses, newest first.
+    async fn list_courses(&self) -> Result<Vec<Course>, sqlx::Error>;
+    /// Courses carrying `tag` (already normalized), newest first.
+    async fn courses_tagged(&self, tag: &str) -> Result<Vec<Course>, sqlx::Error>;
+    async fn sco(&self, id: Uuid) -> Result<Option<Sco>, sqlx::Error>;
+    /// SCOs of a course in manifest order.
+    async fn scos_for_course(&self, course_id: Uuid) -> Result<Vec<Sco>, sqlx::Error>;
+    /// Insert a course row plus one row per parsed SCO.
+    async fn insert_course(
+        &self,
+        id: Uuid,
+        title: &str,
+        base_path: &str,
+        parsed: ParsedManifest,
+    ) -> Result<Course, sqlx::Error>;
+    /// Change one setting; `None` when there is no such course.
+    async fn update_course(
+        &self,
+        id: Uuid,
+        setting: CourseSetting,
+    ) -> Result<Option<Course>, sqlx::Error>;
+    async fn set_sco_comments(
+        &self,
+        sco_id: Uuid,
+        comments: Option<&str>,
+    ) -> Result<Option<Sco>, sqlx::Error>;
+    /// The course's tags, sorted.
+    async fn course_tags(&self, course_id: Uuid) -> Result<Vec<String>, sqlx::Error>;
+    /// Attach tags; ones the course already has are ignored.
+    async fn add_course_tags(&self, course_id: Uuid, tags: &[String]) -> Result<(), sqlx::Error>;
+    async fn remove_course_tags(&self, course_id: Uuid, tags: &[String])
+        -> Result<(), sqlx::Error>;
+}
+
+#[async_trait]
+pub trait AttemptRepo: Send + Sync {
+    async fn attempt(&self, id: Uuid) -> Result<Option<Attempt>, sqlx::Error>;
+    async fn create_attempt(
+        &self,
+        req: &CreateAttemptReq,
+        registration_id: Uuid,
+    ) -> Result<Attempt, sqlx::Error>;
+    /// A registration plus an in-progress attempt per learner, all or nothing.
+    /// Returns the attempt ids in roster order.
+    async fn create_roster(
+        &self,
+        course_id: Uuid,
+        sco_id: Option<Uuid>,
+        learners: &[BulkLearner],
+    ) -> Result<Vec<Uuid>, sqlx::Error>;
+    async fn registration(&self, id: Uuid) -> Result<Option<Registration>, sqlx::Error>;
+    async fn create_registration(
+        &self,
+        course_id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Result<Registration, sqlx::Error>;
+    /// Attempts under a registration, oldest first.
+    async fn registration_attempts(
+        &self,
+        registration_id: Uuid,
+    ) -> Result<Vec<Attempt>, sqlx::Error>;
+    /// A learner's attempts on a course, oldest first.
+    async fn learner_attempts(
+        &self,
+        course_id: Uuid,
+        learner_id: &str,
+    ) -> Result<Vec<Attempt>, sqlx::Error>;
+    async fn patch_attempt(
+        &self,
+        id: Uuid,
+        patch: &AttemptPatch,
+    ) -> Result<Option<Attempt>, sqlx::Error>;
+    /// Back to `in_progress` with finished_at cleared (the admin reopen).
+    async fn reopen_attempt(&self, id: Uuid) -> Result<Option<Attempt>, sqlx::Error>;
+    /// Move an attempt to another learner. The registration follows: renamed when
+    /// the attempt is its only one, otherwise the attempt gets a fresh registration.
+    async fn reassign_attempt(
+        &self,
+        id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Result<Option<Attempt>, sqlx::Error>;
+    /// Instructor override, atomically: completed status with `instructor_override`
+    /// set, and lesson_status `completed` stored (and logged when `audit`).
+    async fn override_complete(
+        &self,
+        id: Uuid,
+        audit: bool,
+    ) -> Result<Option<Attempt>, sqlx::Error>;
+    /// Record `status` and stamp finished_at. Returns the previous status; `None`
+    /// when there is no such attempt.
+    async fn set_status(
+        &self,
+        id: Uuid,
+        status: AttemptStatus,
+    ) -> Result<Option<String>, sqlx::Error>;
+    /// End a session: record `status`, stamp finished_at and add `session_secs` to
+    /// the total time.
+    async fn finish_session(
+        &self,
+        id: Uuid,
+        status: AttemptStatus,
+        session_secs: f64,
+    ) -> Result<(), sqlx::Error>;
+    async fn set_scores(&self, id: Uuid, scores: AttemptScores) -> Result<(), sqlx::Error>;
+    async fn values(&self, attempt_id: Uuid) -> Result<Vec<CmiValue>, sqlx::Error>;
+    async fn value(&self, attempt_id: Uuid, element: &str) -> Result<Option<String>, sqlx::Error>;
+    /// Store a value, `compressed` when it is `runtime::compress_value` output.
+    /// Re-storing the value an element already holds is a no-op (no updated_at
+    /// bump); returns whether anything was written.
+    async fn put_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+        compressed: bool,
+    ) -> Result<bool, sqlx::Error>;
+    /// Delete a value, returning what it held.
+    async fn take_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+    ) -> Result<Option<String>, sqlx::Error>;
+    /// Append a write to cmi_value_log.
+    async fn log_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+    ) -> Result<(), sqlx::Error>;
+    /// The course's cmi_value_log, only writes after `since` when given.
+    fn value_log(&self, course_id: Uuid, since: Option<DateTime<Utc>>) -> ValueLogStream;
+    /// Apply `derive` to every attempt of a course in one transaction; new lesson
+    /// statuses are logged when `audit`. Returns how many attempts changed.
+    async fn rederive_course(
+        &self,
+        course_id: Uuid,
+        derive: &Rederive,
+        audit: bool,
+    ) -> Result<u64, sqlx::Error>;
+}
+
+/// Postgres-backed implementation of both repositories.
+#[derive(Clone)]
+pub struct PgRepo {
+    db: Db,
+}
+
+impl PgRepo {
+    pub fn new(db: Db) -> Self {
+        PgRepo { db }
+    }
+}
+
+#[async_trait]
+impl CourseRepo for PgRepo {
+    async fn course(&self, id: Uuid) -> Result<Option<Course>, sqlx::Error> {
+        query_as!(Course, "SELECT * FROM courses WHERE id=$1", id)
+            .fetch_optional(&self.db)
+            .await
+    }
+
+    async fn list_courses(&self) -> Result<Vec<Course>, sqlx::Error> {
+        query_as!(Course, "SELECT * FROM courses ORDER BY created_at DESC")
+            .fetch_all(&self.db)
+            .await
+    }
+
+    async fn courses_tagged(&self, tag: &str) -> Result<Vec<Course>, sqlx::Error> {
+        query_as!(
+            Course,
+            r#"
+            SELECT c.* FROM courses c
+            WHERE EXISTS (SELECT 1 FROM course_tags t WHERE t.course_id = c.id AND t.tag = $1)
+            ORDER BY c.created_at DESC
+            "#,
+            tag
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn sco(&self, id: Uuid) -> Result<Option<Sco>, sqlx::Error> {
+        query_as!(Sco, "SELECT * FROM scos WHERE id=$1", id)
+            .fetch_optional(&self.db)
+            .await
+    }
+
+    async fn scos_for_course(&self, course_id: Uuid) -> Result<Vec<Sco>, sqlx::Error> {
+        query_as!(
+            Sco,
+            "SELECT * FROM scos WHERE course_id=$1 ORDER BY sort_order, created_at",
+            course_id
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn insert_course(
+        &self,
+        id: Uuid,
+        title: &str,
+        base_path: &str,
+        parsed: ParsedManifest,
+    ) -> Result<Course, sqlx::Error> {
+        let course = query_as!(Course,
+            r#"
+            INSERT INTO courses (id, title, org_identifier, launch_href, base_path, launch_candidates,
+                                 scorm_version, thumbnail_href, mastery_score, description, keywords)
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)
+            RETURNING *
+            "#,
+            id, title, parsed.org_identifier, parsed.default_launch, base_path,
+            &parsed.launch_candidates, parsed.scorm_version.as_str(), parsed.thumbnail_href,
+            parsed.mastery_score, parsed.lom.description, &parsed.lom.keywords
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        for (order, sco) in parsed.scos.into_iter().enumerate() {
+            let mode = sco.control_mode;
+            let _ = query!(
+                r#"
+                INSERT INTO scos (course_id, identifier, launch_href, parameters, sort_order,
+                                  control_choice, control_flow, control_forward_only, is_visible,
+                                  prerequisites, mobile_launch_href, launch_data)
+                VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12)
+                "#,
+                course.id,
+                sco.identifier,
+                sco.href,
+                sco.parameters,
+                order as i32,
+                mode.choice,
+                mode.flow,
+                mode.forward_only,
+                sco.visible,
+                sco.prerequisites,
+                sco.mobile_href,
+                sco.launch_data
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        Ok(course)
+    }
+
+    async fn update_course(
+        &self,
+        id: Uuid,
+        setting: CourseSetting,
+    ) -> Result<Option<Course>, sqlx::Error> {
+        let db = &self.db;
+        match setting {
+            CourseSetting::AllowedElements(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET allowed_elements=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::MinSessionSeconds(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET min_session_seconds=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::MaxAttempts(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET max_attempts=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::MasteryScore(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET mastery_score=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::PassThreshold(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET pass_threshold=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::LaunchStrategy(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET default_launch_strategy=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v.as_str()
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::LaunchMode(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET launch_mode=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v.as_str()
+                )
+                .fetch_optional(db)
+                .await
+            }
+            CourseSetting::CompleteOnScore(v) => {
+                query_as!(
+                    Course,
+                    "UPDATE courses SET complete_on_score=$2 WHERE id=$1 RETURNING *",
+                    id,
+                    v
+                )
+                .fetch_optional(db)
+                .await
+            }
+        }
+    }
+
+    async fn set_sco_comments(
+        &self,
+        sco_id: Uuid,
+        comments: Option<&str>,
+    ) -> Result<Option<Sco>, sqlx::Error> {
+        query_as!(
+            Sco,
+            "UPDATE scos SET comments_from_lms=$2 WHERE id=$1 RETURNING *",
+            sco_id,
+            comments
+        )
+        .fetch_optional(&self.db)
+        .await
+    }
+
+    async fn course_tags(&self, course_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            "SELECT tag FROM course_tags WHERE course_id=$1 ORDER BY tag",
+            course_id
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn add_course_tags(&self, course_id: Uuid, tags: &[String]) -> Result<(), sqlx::Error> {
+        let _ = query!(
+            "INSERT INTO course_tags (course_id, tag) SELECT $1, unnest($2::text[]) ON CONFLICT DO NOTHING",
+            course_id,
+            tags
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_course_tags(
+        &self,
+        course_id: Uuid,
+        tags: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let _ = query!(
+            "DELETE FROM course_tags WHERE course_id=$1 AND tag = ANY($2)",
+            course_id,
+            tags
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AttemptRepo for PgRepo {
+    async fn attempt(&self, id: Uuid) -> Result<Option<Attempt>, sqlx::Error> {
+        query_as!(Attempt, "SELECT * FROM attempts WHERE id=$1", id)
+            .fetch_optional(&self.db)
+            .await
+    }
+
+    async fn create_attempt(
+        &self,
+        req: &CreateAttemptReq,
+        registration_id: Uuid,
+    ) -> Result<Attempt, sqlx::Error> {
+        query_as!(Attempt,
+            r#"
+            INSERT INTO attempts (id, course_id, learner_id, learner_name, sco_id, status, started_at,
+                                  registration_id, language, return_url, metadata)
+            VALUES ($1,$2,$3,$4,$5,$7, now(), $6, $8, $9, $10)
+            RETURNING *
+            "#,
+            Uuid::new_v4(), req.course_id, req.learner_id, req.learner_name, req.sco_id, registration_id,
+            AttemptStatus::InProgress.as_str(), req.language, req.return_url, req.metadata
+        )
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn create_roster(
+        &self,
+        course_id: Uuid,
+        sco_id: Option<Uuid>,
+        learners: &[BulkLearner],
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let mut created = Vec::with_capacity(learners.len());
+        for l in learners {
+            let attempt_id = Uuid::new_v4();
+            let registration_id = sqlx::query_scalar!(
+                "INSERT INTO registrations (course_id, learner_id, learner_name) VALUES ($1,$2,$3) RETURNING id",
+                course_id, l.learner_id, l.learner_name
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            let _ = query!(
+                r#"
+                INSERT INTO attempts (id, course_id, learner_id, learner_name, sco_id, status, started_at,
+                                      registration_id)
+                VALUES ($1,$2,$3,$4,$5,$7, now(), $6)
+                "#,
+                attempt_id, course_id, l.learner_id, l.learner_name, sco_id, registration_id,
+                AttemptStatus::InProgress.as_str()
+            )
+            .execute(&mut *tx)
+            .await?;
+            created.push(attempt_id);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn registration(&self, id: Uuid) -> Result<Option<Registration>, sqlx::Error> {
+        query_as!(Registration, "SELECT * FROM registrations WHERE id=$1", id)
+            .fetch_optional(&self.db)
+            .await
+    }
+
+    async fn create_registration(
+        &self,
+        course_id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Result<Registration, sqlx::Error> {
+        query_as!(Registration,
+            "INSERT INTO registrations (course_id, learner_id, learner_name) VALUES ($1,$2,$3) RETURNING *",
+            course_id, learner_id, learner_name
+        )
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn registration_attempts(
+        &self,
+        registration_id: Uuid,
+    ) -> Result<Vec<Attempt>, sqlx::Error> {
+        query_as!(
+            Attempt,
+            "SELECT * FROM attempts WHERE registration_id=$1 ORDER BY created_at",
+            registration_id
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn learner_attempts(
+        &self,
+        course_id: Uuid,
+        learner_id: &str,
+    ) -> Result<Vec<Attempt>, sqlx::Error> {
+        query_as!(
+            Attempt,
+            "SELECT * FROM attempts WHERE course_id=$1 AND learner_id=$2 ORDER BY created_at",
+            course_id,
+            learner_id
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn patch_attempt(
+        &self,
+        id: Uuid,
+        patch: &AttemptPatch,
+    ) -> Result<Option<Attempt>, sqlx::Error> {
+        query_as!(
+            Attempt,
+            r#"
+            UPDATE attempts SET
+                status = COALESCE($2, status),
+                learner_name = CASE WHEN $3::text IS NULL THEN learner_name ELSE NULLIF($3, '') END,
+                finished_at = COALESCE($4, finished_at)
+            WHERE id=$1
+            RETURNING *
+            "#,
+            id,
+            patch.status.map(|s| s.as_str()),
+            patch.learner_name.as_deref(),
+            patch.finished_at
+        )
+        .fetch_optional(&self.db)
+        .await
+    }
+
+    async fn reopen_attempt(&self, id: Uuid) -> Result<Option<Attempt>, sqlx::Error> {
+        query_as!(
+            Attempt,
+            "UPDATE attempts SET status=$2, finished_at=NULL WHERE id=$1 RETURNING *",
+            id,
+            AttemptStatus::InProgress.as_str()
+        )
+        .fetch_optional(&self.db)
+        .await
+    }
+
+    async fn reassign_attempt(
+        &self,
+        id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Result<Option<Attempt>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let Some(current) = query!(
+            r#"
+            SELECT a.course_id, a.registration_id,
+                   (SELECT count(*) FROM attempts o WHERE o.registration_id = a.registration_id) AS "siblings!"
+            FROM attempts a WHERE a.id=$1
+            FOR UPDATE OF a
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let registration_id = if current.siblings <= 1 {
+            let _ = query!(
+                "UPDATE registrations SET learner_id=$2, learner_name=$3 WHERE id=$1",
+                current.registration_id,
+                learner_id,
+                learner_name
+            )
+            .execute(&mut *tx)
+            .await?;
+            current.registration_id
+        } else {
+            sqlx::query_scalar!(
+                "INSERT INTO registrations (course_id, learner_id, learner_name) VALUES ($1,$2,$3) RETURNING id",
+                current.course_id,
+                learner_id,
+                learner_name
+            )
+            .fetch_one(&mut *tx)
+            .await?
+        };
+        let rec = query_as!(Attempt,
+            "UPDATE attempts SET learner_id=$2, learner_name=$3, registration_id=$4 WHERE id=$1 RETURNING *",
+            id,
+            learner_id,
+            learner_name,
+            registration_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(rec))
+    }
+
+    async fn override_complete(
+        &self,
+        id: Uuid,
+        audit: bool,
+    ) -> Result<Option<Attempt>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let Some(rec) = query_as!(
+            Attempt,
+            r#"
+            UPDATE attempts SET status=$2, finished_at=now(), instructor_override=true
+            WHERE id=$1
+            RETURNING *
+            "#,
+            id,
+            AttemptStatus::Completed.as_str()
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let _ = query!(
+            r#"
+            INSERT INTO cmi_values (attempt_id, element, value)
+            VALUES ($1, 'cmi.core.lesson_status', 'completed')
+            ON CONFLICT (attempt_id, element)
+            DO UPDATE SET value=EXCLUDED.value, updated_at=now()
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if audit {
+            let _ = query!(
+                "INSERT INTO cmi_value_log (attempt_id, element, value) VALUES ($1, 'cmi.core.lesson_status', 'completed')",
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(Some(rec))
+    }
+
+    async fn set_status(
+        &self,
+        id: Uuid,
+        status: AttemptStatus,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE attempts a SET status=$2, finished_at=now()
+            FROM attempts old WHERE a.id=$1 AND old.id=a.id
+            RETURNING old.status
+            "#,
+            id,
+            status.as_str()
+        )
+        .fetch_optional(&self.db)
+        .await
+    }
+
+    async fn finish_session(
+        &self,
+        id: Uuid,
+        status: AttemptStatus,
+        session_secs: f64,
+    ) -> Result<(), sqlx::Error> {
+        let _ = query!(
+            r#"
+            UPDATE attempts SET status=$2, finished_at=now(), total_time_secs = total_time_secs + $3
+            WHERE id=$1
+            "#,
+            id,
+            status.as_str(),
+            session_secs
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn set_scores(&self, id: Uuid, scores: AttemptScores) -> Result<(), sqlx::Error> {
+        let _ = query!(
+            "UPDATE attempts SET score_raw=$2, score_min=$3, score_max=$4, score_scaled=$5 WHERE id=$1",
+            id,
+            scores.raw,
+            scores.min,
+            scores.max,
+            scores.scaled
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn values(&self, attempt_id: Uuid) -> Result<Vec<CmiValue>, sqlx::Error> {
+        query_as!(
+            CmiValue,
+            "SELECT * FROM cmi_values WHERE attempt_id=$1",
+            attempt_id
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn value(&self, attempt_id: Uuid, element: &str) -> Result<Option<String>, sqlx::Error> {
+        Ok(sqlx::query_scalar!(
+            "SELECT value FROM cmi_values WHERE attempt_id=$1 AND element=$2",
+            attempt_id,
+            element
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .flatten())
+    }
+
+    async fn put_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+        compressed: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let written = query!(
+            r#"
+            INSERT INTO cmi_values (attempt_id, element, value, compressed)
+            VALUES ($1,$2,$3,$4)
+            ON CONFLICT (attempt_id, element)
+            DO UPDATE SET value=EXCLUDED.value, compressed=EXCLUDED.compressed, updated_at=now()
+            WHERE cmi_values.value IS DISTINCT FROM EXCLUDED.value
+               OR cmi_values.compressed <> EXCLUDED.compressed
+            "#,
+            attempt_id,
+            element,
+            value,
+            compressed
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+        Ok(written > 0)
+    }
+
+    async fn take_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        Ok(sqlx::query_scalar!(
+            "DELETE FROM cmi_values WHERE attempt_id=$1 AND element=$2 RETURNING value",
+            attempt_id,
+            element
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .flatten())
+    }
+
+    async fn log_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+    ) -> Result<(), sqlx::Error> {
+        let _ = query!(
+            "INSERT INTO cmi_value_log (attempt_id, element, value) VALUES ($1,$2,$3)",
+            attempt_id,
+            element,
+            value
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    fn value_log(&self, course_id: Uuid, since: Option<DateTime<Utc>>) -> ValueLogStream {
+        // rows are pumped by a task so the stream doesn't borrow the pool
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            let mut rows = query_as!(
+                ValueLogEntry,
+                r#"
+                SELECT l.id, l.attempt_id, a.learner_id, l.element, l.value, l.logged_at
+                FROM cmi_value_log l JOIN attempts a ON a.id = l.attempt_id
+                WHERE a.course_id=$1 AND ($2::timestamptz IS NULL OR l.logged_at > $2)
+                ORDER BY l.logged_at, l.id
+                "#,
+                course_id,
+                since
+            )
+            .fetch(&db);
+            while let Some(row) = rows.next().await {
+                let failed = row.is_err();
+                if tx.send(row).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx))
+    }
+
+    async fn rederive_course(
+        &self,
+        course_id: Uuid,
+        derive: &Rederive,
+        audit: bool,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let attempts = query_as!(
+            Attempt,
+            "SELECT * FROM attempts WHERE course_id=$1 FOR UPDATE",
+            course_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut changed = 0;
+        for attempt in attempts {
+            let values = query_as!(
+                CmiValue,
+                "SELECT * FROM cmi_values WHERE attempt_id=$1",
+                attempt.id
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            let update = derive(&attempt, &values);
+            if let Some(lesson_status) = &update.lesson_status {
+                let _ = query!(
+                    r#"
+                    INSERT INTO cmi_values (attempt_id, element, value)
+                    VALUES ($1, 'cmi.core.lesson_status', $2)
+                    ON CONFLICT (attempt_id, element)
+                    DO UPDATE SET value=EXCLUDED.value, updated_at=now()
+                    "#,
+                    attempt.id,
+                    lesson_status
+                )
+                .execute(&mut *tx)
+                .await?;
+                if audit {
+                    let _ = query!(
+                        "INSERT INTO cmi_value_log (attempt_id, element, value) VALUES ($1, 'cmi.core.lesson_status', $2)",
+                        attempt.id,
+                        lesson_status
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+            if let Some(status) = update.status {
+                let _ = query!(
+                    "UPDATE attempts SET status=$2, finished_at=COALESCE(finished_at, now()) WHERE id=$1",
+                    attempt.id,
+                    status.as_str()
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            if update.lesson_status.is_some() || update.status.is_some() {
+                changed += 1;
+            }
+        }
+        tx.commit().await?;
+        Ok(changed)
+    }
+}
@@ -0,0 +1,646 @@
+// In-memory `CourseRepo`/`AttemptRepo` for route tests: no Postgres needed.
+// Mirrors the column defaults and ordering of the Postgres implementation.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::*;
+
+#[derive(Default)]
+pub struct MemRepo {
+    state: Mutex<MemState>,
+}
+
+#[derive(Default)]
+struct MemState {
+    courses: Vec<Course>,
+    scos: Vec<Sco>,
+    tags: Vec<(Uuid, String)>,
+    registrations: Vec<Registration>,
+    attempts: Vec<Attempt>,
+    values: Vec<CmiValue>,
+    log: Vec<ValueLogEntry>,
+}
+
+impl MemState {
+    fn attempt_mut(&mut self, id: Uuid) -> Option<&mut Attempt> {
+        self.attempts.iter_mut().find(|a| a.id == id)
+    }
+
+    fn new_registration(
+        &mut self,
+        course_id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Registration {
+        let reg = Registration {
+            id: Uuid::new_v4(),
+            course_id,
+            learner_id: learner_id.to_string(),
+            learner_name: learner_name.map(String::from),
+            created_at: Utc::now(),
+        };
+        self.registrations.push(reg.clone());
+        reg
+    }
+
+    fn new_attempt(
+        &mut self,
+        course_id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+        sco_id: Option<Uuid>,
+        registration_id: Uuid,
+    ) -> &mut Attempt {
+        let now = Utc::now();
+        self.attempts.push(Attempt {
+            id: Uuid::new_v4(),
+            course_id,
+            learner_id: learner_id.to_string(),
+            sco_id,
+            status: AttemptStatus::InProgress.as_str().to_string(),
+            started_at: Some(now),
+            finished_at: None,
+            created_at: now,
+            learner_name: learner_name.map(String::from),
+            instructor_override: false,
+            total_time_secs: 0.0,
+            registration_id,
+            score_raw: None,
+            score_min: None,
+            score_max: None,
+            score_scaled: None,
+            random_seed: (Uuid::new_v4().as_u128() % 2_147_483_647) as i64,
+            language: None,
+            return_url: None,
+            metadata: None,
+        });
+        self.attempts.last_mut().expect("just pushed")
+    }
+
+    fn put_value(
+        &mut self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+        compressed: bool,
+        force: bool,
+    ) -> bool {
+        let now = Utc::now();
+        match self
+            .values
+            .iter_mut()
+            .find(|v| v.attempt_id == attempt_id && v.element == element)
+        {
+            Some(v)
+                if !force && v.value.as_deref() == Some(value) && v.compressed == compressed =>
+            {
+                false
+            }
+            Some(v) => {
+                v.value = Some(value.to_string());
+                v.compressed = compressed;
+                v.updated_at = now;
+                true
+            }
+            None => {
+                self.values.push(CmiValue {
+                    attempt_id,
+                    element: element.to_string(),
+                    value: Some(value.to_string()),
+                    updated_at: now,
+                    compressed,
+                });
+                true
+            }
+        }
+    }
+
+    fn log_value(&mut self, attempt_id: Uuid, element: &str, value: &str) {
+        let learner_id = self
+            .attempts
+            .iter()
+            .find(|a| a.id == attempt_id)
+            .map(|a| a.learner_id.clone())
+            .unwrap_or_default();
+        self.log.push(ValueLogEntry {
+            id: self.log.len() as i64 + 1,
+            attempt_id,
+            learner_id,
+            element: element.to_string(),
+            value: Some(value.to_string()),
+            logged_at: Utc::now(),
+        });
+    }
+}
+
+impl MemRepo {
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemState> {
+        self.state.lock().expect("MemRepo lock poisoned")
+    }
+}
+
+#[async_trait]
+impl CourseRepo for MemRepo {
+    async fn course(&self, id: Uuid) -> Result<Option<Course>, sqlx::Error> {
+        Ok(self.lock().courses.iter().find(|c| c.id == id).cloned())
+    }
+
+    async fn list_courses(&self) -> Result<Vec<Course>, sqlx::Error> {
+        let mut courses = self.lock().courses.clone();
+        courses.reverse();
+        Ok(courses)
+    }
+
+    async fn courses_tagged(&self, tag: &str) -> Result<Vec<Course>, sqlx::Error> {
+        let state = self.lock();
+        Ok(state
+            .courses
+            .iter()
+            .rev()
+            .filter(|c| state.tags.iter().any(|(id, t)| *id == c.id && t == tag))
+            .cloned()
+            .collect())
+    }
+
+    async fn sco(&self, id: Uuid) -> Result<Option<Sco>, sqlx::Error> {
+        Ok(self.lock().scos.iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn scos_for_course(&self, course_id: Uuid) -> Result<Vec<Sco>, sqlx::Error> {
+        let mut scos: Vec<Sco> = self
+            .lock()
+            .scos
+            .iter()
+            .filter(|s| s.course_id == course_id)
+            .cloned()
+            .collect();
+        scos.sort_by_key(|s| s.sort_order);
+        Ok(scos)
+    }
+
+    async fn insert_course(
+        &self,
+        id: Uuid,
+        title: &str,
+        base_path: &str,
+        parsed: ParsedManifest,
+    ) -> Result<Course, sqlx::Error> {
+        let now = Utc::now();
+        let course = Course {
+            id,
+            title: title.to_string(),
+            org_identifier: parsed.org_identifier,
+            launch_href: parsed.default_launch,
+            base_path: base_path.to_string(),
+            created_at: now,
+            launch_candidates: parsed.launch_candidates,
+            scorm_version: parsed.scorm_version.as_str().to_string(),
+            allowed_elements: None,
+            thumbnail_href: parsed.thumbnail_href,
+            min_session_seconds: None,
+            mastery_score: parsed.mastery_score,
+            max_attempts: None,
+            pass_threshold: None,
+            default_launch_strategy: LaunchStrategy::default().as_str().to_string(),
+            complete_on_score: false,
+            description: parsed.lom.description,
+            keywords: parsed.lom.keywords,
+            launch_mode: LaunchMode::default().as_str().to_string(),
+        };
+        let mut state = self.lock();
+        for (order, sco) in parsed.scos.into_iter().enumerate() {
+            state.scos.push(Sco {
+                id: Uuid::new_v4(),
+                course_id: id,
+                identifier: sco.identifier,
+                launch_href: sco.href,
+                parameters: sco.parameters,
+                created_at: now,
+                sort_order: order as i32,
+                control_choice: sco.control_mode.choice,
+                control_flow: sco.control_mode.flow,
+                control_forward_only: sco.control_mode.forward_only,
+                comments_from_lms: None,
+                is_visible: sco.visible,
+                prerequisites: sco.prerequisites,
+                mobile_launch_href: sco.mobile_href,
+                launch_data: sco.launch_data,
+            });
+        }
+        state.courses.push(course.clone());
+        Ok(course)
+    }
+
+    async fn update_course(
+        &self,
+        id: Uuid,
+        setting: CourseSetting,
+    ) -> Result<Option<Course>, sqlx::Error> {
+        let mut state = self.lock();
+        let Some(c) = state.courses.iter_mut().find(|c| c.id == id) else {
+            return Ok(None);
+        };
+        match setting {
+            CourseSetting::AllowedElements(v) => c.allowed_elements = v,
+            CourseSetting::MinSessionSeconds(v) => c.min_session_seconds = v,
+            CourseSetting::MaxAttempts(v) => c.max_attempts = v,
+            CourseSetting::MasteryScore(v) => c.mastery_score = v,
+            CourseSetting::PassThreshold(v) => c.pass_threshold = v,
+            CourseSetting::LaunchStrategy(v) => c.default_launch_strategy = v.as_str().into(),
+            CourseSetting::LaunchMode(v) => c.launch_mode = v.as_str().into(),
+            CourseSetting::CompleteOnScore(v) => c.complete_on_score = v,
+        }
+        Ok(Some(c.clone()))
+    }
+
+    async fn set_sco_comments(
+        &self,
+        sco_id: Uuid,
+        comments: Option<&str>,
+    ) -> Result<Option<Sco>, sqlx::Error> {
+        let mut state = self.lock();
+        Ok(state.scos.iter_mut().find(|s| s.id == sco_id).map(|s| {
+            s.comments_from_lms = comments.map(String::from);
+            s.clone()
+        }))
+    }
+
+    async fn course_tags(&self, course_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        let mut tags: Vec<String> = self
+            .lock()
+            .tags
+            .iter()
+            .filter(|(id, _)| *id == course_id)
+            .map(|(_, t)| t.clone())
+            .collect();
+        tags.sort();
+        Ok(tags)
+    }
+
+    async fn add_course_tags(&self, course_id: Uuid, tags: &[String]) -> Result<(), sqlx::Error> {
+        let mut state = self.lock();
+        for tag in tags {
+            if !state
+                .tags
+                .iter()
+                .any(|(id, t)| *id == course_id && t == tag)
+            {
+                state.tags.push((course_id, tag.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_course_tags(
+        &self,
+        course_id: Uuid,
+        tags: &[String],
+    ) -> Result<(), sqlx::Error> {
+        self.lock()
+            .tags
+            .retain(|(id, t)| *id != course_id || !tags.contains(t));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AttemptRepo for MemRepo {
+    async fn attempt(&self, id: Uuid) -> Result<Option<Attempt>, sqlx::Error> {
+        Ok(self.lock().attempts.iter().find(|a| a.id == id).cloned())
+    }
+
+    async fn create_attempt(
+        &self,
+        req: &CreateAttemptReq,
+        registration_id: Uuid,
+    ) -> Result<Attempt, sqlx::Error> {
+        let mut state = self.lock();
+        let a = state.new_attempt(
+            req.course_id,
+            &req.learner_id,
+            req.learner_name.as_deref(),
+            req.sco_id,
+            registration_id,
+        );
+        a.language = req.language.clone();
+        a.return_url = req.return_url.clone();
+        a.metadata = req.metadata.clone();
+        Ok(a.clone())
+    }
+
+    async fn create_roster(
+        &self,
+        course_id: Uuid,
+        sco_id: Option<Uuid>,
+        learners: &[BulkLearner],
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let mut state = self.lock();
+        let mut created = Vec::with_capacity(learners.len());
+        for l in learners {
+            let name = l.learner_name.as_deref();
+            let reg = state.new_registration(course_id, &l.learner_id, name);
+            let id = state
+                .new_attempt(course_id, &l.learner_id, name, sco_id, reg.id)
+                .id;
+            created.push(id);
+        }
+        Ok(created)
+    }
+
+    async fn registration(&self, id: Uuid) -> Result<Option<Registration>, sqlx::Error> {
+        Ok(self
+            .lock()
+            .registrations
+            .iter()
+            .find(|r| r.id == id)
+            .cloned())
+    }
+
+    async fn create_registration(
+        &self,
+        course_id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Result<Registration, sqlx::Error> {
+        Ok(self
+            .lock()
+            .new_registration(course_id, learner_id, learner_name))
+    }
+
+    async fn registration_attempts(
+        &self,
+        registration_id: Uuid,
+    ) -> Result<Vec<Attempt>, sqlx::Error> {
+        Ok(self
+            .lock()
+            .attempts
+            .iter()
+            .filter(|a| a.registration_id == registration_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn learner_attempts(
+        &self,
+        course_id: Uuid,
+        learner_id: &str,
+    ) -> Result<Vec<Attempt>, sqlx::Error> {
+        Ok(self
+            .lock()
+            .attempts
+            .iter()
+            .filter(|a| a.course_id == course_id && a.learner_id == learner_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn patch_attempt(
+        &self,
+        id: Uuid,
+        patch: &AttemptPatch,
+    ) -> Result<Option<Attempt>, sqlx::Error> {
+        let mut state = self.lock();
+        let Some(a) = state.attempt_mut(id) else {
+            return Ok(None);
+        };
+        if let Some(status) = patch.status {
+            a.status = status.as_str().to_string();
+        }
+        if let Some(name) = &patch.learner_name {
+            a.learner_name = Some(name.clone()).filter(|n| !n.is_empty());
+        }
+        if let Some(finished_at) = patch.finished_at {
+            a.finished_at = Some(finished_at);
+        }
+        Ok(Some(a.clone()))
+    }
+
+    async fn reopen_attempt(&self, id: Uuid) -> Result<Option<Attempt>, sqlx::Error> {
+        let mut state = self.lock();
+        let Some(a) = state.attempt_mut(id) else {
+            return Ok(None);
+        };
+        a.status = AttemptStatus::InProgress.as_str().to_string();
+        a.finished_at = None;
+        Ok(Some(a.clone()))
+    }
+
+    async fn reassign_attempt(
+        &self,
+        id: Uuid,
+        learner_id: &str,
+        learner_name: Option<&str>,
+    ) -> Result<Option<Attempt>, sqlx::Error> {
+        let mut state = self.lock();
+        let Some((course_id, registration_id)) = state
+            .attempts
+            .iter()
+            .find(|a| a.id == id)
+            .map(|a| (a.course_id, a.registration_id))
+        else {
+            return Ok(None);
+        };
+        let siblings = state
+            .attempts
+            .iter()
+            .filter(|a| a.registration_id == registration_id)
+            .count();
+        let registration_id = if siblings <= 1 {
+            if let Some(r) = state
+                .registrations
+                .iter_mut()
+                .find(|r| r.id == registration_id)
+            {
+                r.learner_id = learner_id.to_string();
+                r.learner_name = learner_name.map(String::from);
+            }
+            registration_id
+        } else {
+            state
+                .new_registration(course_id, learner_id, learner_name)
+                .id
+        };
+        let a = state.attempt_mut(id).expect("checked above");
+        a.learner_id = learner_id.to_string();
+        a.learner_name = learner_name.map(String::from);
+        a.registration_id = registration_id;
+        Ok(Some(a.clone()))
+    }
+
+    async fn override_complete(
+        &self,
+        id: Uuid,
+        audit: bool,
+    ) -> Result<Option<Attempt>, sqlx::Error> {
+        let mut state = self.lock();
+        let Some(a) = state.attempt_mut(id) else {
+            return Ok(None);
+        };
+        a.status = AttemptStatus::Completed.as_str().to_string();
+        a.finished_at = Some(Utc::now());
+        a.instructor_override = true;
+        let rec = a.clone();
+        state.put_value(id, "cmi.core.lesson_status", "completed", false, true);
+        if audit {
+            state.log_value(id, "cmi.core.lesson_status", "completed");
+        }
+        Ok(Some(rec))
+    }
+
+    async fn set_status(
+        &self,
+        id: Uuid,
+        status: AttemptStatus,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let mut state = self.lock();
+        Ok(state.attempt_mut(id).map(|a| {
+            a.finished_at = Some(Utc::now());
+            std::mem::replace(&mut a.status, status.as_str().to_string())
+        }))
+    }
+
+    async fn finish_session(
+        &self,
+        id: Uuid,
+        status: AttemptStatus,
+        session_secs: f64,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(a) = self.lock().attempt_mut(id) {
+            a.status = status.as_str().to_string();
+            a.finished_at = Some(Utc::now());
+            a.total_time_secs += session_secs;
+        }
+        Ok(())
+    }
+
+    async fn set_scores(&self, id: Uuid, scores: AttemptScores) -> Result<(), sqlx::Error> {
+        if let Some(a) = self.lock().attempt_mut(id) {
+            a.score_raw = scores.raw;
+            a.score_min = scores.min;
+            a.score_max = scores.max;
+            a.score_scaled = scores.scaled;
+        }
+        Ok(())
+    }
+
+    async fn values(&self, attempt_id: Uuid) -> Result<Vec<CmiValue>, sqlx::Error> {
+        Ok(self
+            .lock()
+            .values
+            .iter()
+            .filter(|v| v.attempt_id == attempt_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn value(&self, attempt_id: Uuid, element: &str) -> Result<Option<String>, sqlx::Error> {
+        Ok(self
+            .lock()
+            .values
+            .iter()
+            .find(|v| v.attempt_id == attempt_id && v.element == element)
+            .and_then(|v| v.value.clone()))
+    }
+
+    async fn put_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+        compressed: bool,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .lock()
+            .put_value(attempt_id, element, value, compressed, false))
+    }
+
+    async fn take_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let mut state = self.lock();
+        let pos = state
+            .values
+            .iter()
+            .position(|v| v.attempt_id == attempt_id && v.element == element);
+        Ok(pos.and_then(|i| state.values.remove(i).value))
+    }
+
+    async fn log_value(
+        &self,
+        attempt_id: Uuid,
+        element: &str,
+        value: &str,
+    ) -> Result<(), sqlx::Error> {
+        self.lock().log_value(attempt_id, element, value);
+        Ok(())
+    }
+
+    fn value_log(&self, course_id: Uuid, since: Option<DateTime<Utc>>) -> ValueLogStream {
+        let state = self.lock();
+        let rows: Vec<_> = state
+            .log
+            .iter()
+            .filter(|l| {
+                state
+                    .attempts
+                    .iter()
+                    .any(|a| a.id == l.attempt_id && a.course_id == course_id)
+            })
+            .filter(|l| since.is_none_or(|s| l.logged_at > s))
+            .cloned()
+            .map(Ok)
+            .collect();
+        Box::pin(tokio_stream::iter(rows))
+    }
+
+    async fn rederive_course(
+        &self,
+        course_id: Uuid,
+        derive: &Rederive,
+        audit: bool,
+    ) -> Result<u64, sqlx::Error> {
+        let mut state = self.lock();
+        let attempts: Vec<Attempt> = state
+            .attempts
+            .iter()
+            .filter(|a| a.course_id == course_id)
+            .cloned()
+            .collect();
+        let mut changed = 0;
+        for attempt in attempts {
+            let values: Vec<CmiValue> = state
+                .values
+                .iter()
+                .filter(|v| v.attempt_id == attempt.id)
+                .cloned()
+                .collect();
+            let update = derive(&attempt, &values);
+            if let Some(lesson_status) = &update.lesson_status {
+                state.put_value(
+                    attempt.id,
+                    "cmi.core.lesson_status",
+                    lesson_status,
+                    false,
+                    true,
+                );
+                if audit {
+                    state.log_value(attempt.id, "cmi.core.lesson_status", lesson_status);
+                }
+            }
+            if let Some(status) = update.status {
+                let a = state.attempt_mut(attempt.id).expect("listed above");
+                a.status = status.as_str().to_string();
+                a.finished_at.get_or_insert_with(Utc::now);
+            }
+            if update.lesson_status.is_some() || update.status.is_some() {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+}
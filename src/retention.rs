@@ -0,0 +1,169 @@
+// Periodic purge of detailed runtime data for long-finished attempts
+// (`RETENTION_DAYS`). The attempt row keeps its status and score summary.
+
+use std::time::Duration;
+
+use crate::{db::Db, models::AttemptStatus};
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetentionPolicy {
+    pub days: u32,
+    pub interval: Duration,
+    // also drop the attempts' cmi_value_log history
+    pub purge_log: bool,
+}
+
+/// Rows removed by one retention pass.
+#[derive(Debug, Default)]
+pub struct Purged {
+    pub values: u64,
+    pub log: u64,
+}
+
+/// Delete `cmi_values` (and optionally `cmi_value_log`) of attempts that finished
+/// as completed, failed or timed out more than `policy.days` ago.
+pub async fn run_pass(db: &Db, policy: &RetentionPolicy) -> Result<Purged, sqlx::Error> {
+    let finished = [
+        AttemptStatus::Completed.as_str(),
+        AttemptStatus::Failed.as_str(),
+        AttemptStatus::TimedOut.as_str(),
+    ]
+    .map(String::from);
+    let days = policy.days as i32;
+    let mut tx = db.begin().await?;
+    let values = sqlx::query!(
+        r#"
+        DELETE FROM cmi_values v USING attempts a
+        WHERE v.attempt_id = a.id AND a.status = ANY($1)
+          AND a.finished_at < now() - make_interval(days => $2)
+        "#,
+        &finished,
+        days
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let log = if policy.purge_log {
+        sqlx::query!(
+            r#"
+            DELETE FROM cmi_value_log l USING attempts a
+            WHERE l.attempt_id = a.id AND a.status = ANY($1)
+              AND a.finished_at < now() - make_interval(days => $2)
+            "#,
+            &finished,
+            days
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+    } else {
+        0
+    };
+    tx.commit().await?;
+    Ok(Purged { values, log })
+}
+
+/// Run `run_pass` every `policy.interval` in the background.
+pub fn spawn(db: Db, policy: RetentionPolicy) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(policy.interval);
+        loop {
+            tick.tick().await;
+            match run_pass(&db, &policy).await {
+                Ok(p) if p.values + p.log > 0 => tracing::info!(
+                    "retention: purged {} cmi values and {} log rows older than {} days",
+                    p.values,
+                    p.log,
+                    policy.days
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!(error=%e, "retention pass failed"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Attempt with one value and one log row, finished `days_ago` days back.
+    async fn finished_attempt(db: &Db, status: &str, days_ago: i32) -> uuid::Uuid {
+        let (course_id,): (uuid::Uuid,) = sqlx::query_as(
+            "INSERT INTO courses (title, launch_href, base_path) VALUES ('T', 'index.html', 'x') RETURNING id",
+        )
+        .fetch_one(db)
+        .await
+        .unwrap();
+        let (registration_id,): (uuid::Uuid,) = sqlx::query_as(
+            "INSERT INTO registrations (course_id, learner_id) VALUES ($1, 'l') RETURNING id",
+        )
+        .bind(course_id)
+        .fetch_one(db)
+        .await
+        .unwrap();
+        let (attempt_id,): (uuid::Uuid,) = sqlx::query_as(
+            "INSERT INTO attempts (course_id, learner_id, registration_id, status, score_raw, finished_at)
+             VALUES ($1, 'l', $2, $3, 90, now() - make_interval(days => $4)) RETURNING id",
+        )
+        .bind(course_id)
+        .bind(registration_id)
+        .bind(status)
+        .bind(days_ago)
+        .fetch_one(db)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO cmi_values (attempt_id, element, value) VALUES ($1, 'cmi.core.lesson_status', 'passed')")
+            .bind(attempt_id)
+            .execute(db)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO cmi_value_log (attempt_id, element, value) VALUES ($1, 'cmi.core.lesson_status', 'passed')")
+            .bind(attempt_id)
+            .execute(db)
+            .await
+            .unwrap();
+        attempt_id
+    }
+
+    async fn count(db: &Db, table: &str, attempt_id: uuid::Uuid) -> i64 {
+        sqlx::query_scalar(&format!(
+            "SELECT count(*) FROM {table} WHERE attempt_id = $1"
+        ))
+        .bind(attempt_id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn pass_purges_old_finished_attempts_but_keeps_their_summary(db: Db) {
+        let old = finished_attempt(&db, "completed", 40).await;
+        let recent = finished_attempt(&db, "completed", 1).await;
+        let old_open = finished_attempt(&db, "in_progress", 40).await;
+        let old_failed = finished_attempt(&db, "failed", 40).await;
+        let policy = RetentionPolicy {
+            days: 30,
+            interval: Duration::from_secs(3600),
+            purge_log: true,
+        };
+
+        let purged = run_pass(&db, &policy).await.unwrap();
+        assert_eq!((purged.values, purged.log), (2, 2));
+        for gone in [old, old_failed] {
+            assert_eq!(count(&db, "cmi_values", gone).await, 0);
+            assert_eq!(count(&db, "cmi_value_log", gone).await, 0);
+        }
+        for kept in [recent, old_open] {
+            assert_eq!(count(&db, "cmi_values", kept).await, 1);
+            assert_eq!(count(&db, "cmi_value_log", kept).await, 1);
+        }
+        let (status, score): (String, Option<f64>) =
+            sqlx::query_as("SELECT status, score_raw FROM attempts WHERE id = $1")
+                .bind(old)
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!((status.as_str(), score), ("completed", Some(90.0)));
+    }
+}
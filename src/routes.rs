@@ -1,342 +1,6734 @@
+use crate::{
+    config::Config,
+    db::Db,
+    fetch, manifest,
+    models::*,
+    player,
+    ratelimit::{self, WriteLimiter},
+    repo::{
+        AttemptPatch, AttemptRepo, AttemptScores, CourseRepo, CourseSetting, PgRepo, Rederived,
+    },
+    runtime, util,
+};
+use axum::http::StatusCode;
 use axum::{
-    extract::{Multipart, Path, State},
-    response::{Html, IntoResponse},
-    routing::{get, post},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    handler::HandlerWithoutStateExt,
+    http::{header, HeaderMap, HeaderValue},
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
+    routing::{get, post, put},
     Json, Router,
 };
-use sqlx::{query, query_as};
-use std::path::PathBuf;
-use tower_http::services::ServeDir;
+use chrono::Timelike;
+use std::{convert::Infallible, sync::Arc};
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
 use uuid::Uuid;
-use axum::http::StatusCode;
-use crate::{db::Db, manifest, models::*, runtime};
 
-pub fn router(db: Db) -> Router {
-    let static_dir = std::env::var("DATA_DIR").unwrap_or("./data".into());
+/// Shared handler state. All stored state goes through the repositories, so
+/// tests can run the routes on `repo::memory::MemRepo`.
+#[derive(Clone)]
+pub struct AppState {
+    pub courses: Arc<dyn CourseRepo>,
+    pub attempts: Arc<dyn AttemptRepo>,
+    // value/status changes for SSE subscribers; sends with no subscribers are dropped
+    pub events: broadcast::Sender<AttemptEvent>,
+    pub config: Arc<Config>,
+    // None when RUNTIME_WRITES_PER_SECOND is 0
+    pub write_limiter: Option<Arc<WriteLimiter>>,
+}
+
+impl AppState {
+    pub fn new(db: Db, config: Config) -> Self {
+        AppState::with_repo(Arc::new(PgRepo::new(db)), config)
+    }
+
+    /// State over any implementation of both repositories.
+    pub fn with_repo<R: CourseRepo + AttemptRepo + 'static>(repo: Arc<R>, config: Config) -> Self {
+        let (events, _) = broadcast::channel(256);
+        let write_limiter = (config.runtime_writes_per_second > 0).then(|| {
+            let limiter = Arc::new(WriteLimiter::new(config.runtime_writes_per_second));
+            ratelimit::spawn_sweeper(limiter.clone());
+            limiter
+        });
+        AppState {
+            courses: repo.clone(),
+            attempts: repo,
+            events,
+            config: Arc::new(config),
+            write_limiter,
+        }
+    }
+
+    fn check_write_rate(&self, attempt_id: Uuid) -> Result<(), (axum::http::StatusCode, String)> {
+        match &self.write_limiter {
+            Some(l) if !l.allow(attempt_id) => Err((
+                axum::http::StatusCode::TOO_MANY_REQUESTS,
+                "too many runtime writes for this attempt".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// With LOCK_FINISHED_ATTEMPTS, 409 for writes to an attempt in a finished status.
+    async fn check_writable(
+        &self,
+        attempt_id: Uuid,
+    ) -> Result<(), (axum::http::StatusCode, String)> {
+        if !self.config.lock_finished_attempts {
+            return Ok(());
+        }
+        let attempt = self.attempts.attempt(attempt_id).await.map_err(e500)?;
+        match attempt.and_then(|a| a.status.parse::<AttemptStatus>().ok()) {
+            Some(s) if s.is_finished() => Err((
+                axum::http::StatusCode::CONFLICT,
+                format!("attempt is {}; runtime writes are locked", s),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn publish(&self, attempt_id: Uuid, kind: &'static str, data: serde_json::Value) {
+        let _ = self.events.send(AttemptEvent {
+            attempt_id,
+            kind,
+            data,
+        });
+    }
+}
+
+pub fn router(state: AppState) -> Router {
+    let files =
+        ServeDir::new(&state.config.data_dir).not_found_service(content_not_found.into_service());
+    let mut content = Router::new().fallback_service(files);
+    if state.config.content_utf8_charset {
+        content = content.layer(middleware::map_response(default_utf8_charset));
+    }
+    // commits are small; a runaway SCO hits this long before the upload-sized global limit
+    let runtime_limit = state.config.runtime_body_limit;
+    let runtime = Router::new()
+        .route("/runtime/:attempt_id/initialize", post(rt_initialize))
+        .route("/runtime/:attempt_id/set", post(rt_set))
+        .route("/runtime/:attempt_id/get", post(rt_get))
+        .route("/runtime/:attempt_id/commit", post(rt_commit))
+        .route("/runtime/commit_batch", post(rt_commit_batch))
+        .route("/runtime/:attempt_id/finish", post(rt_finish))
+        .route("/runtime/:attempt_id/suspend", post(rt_suspend))
+        .route("/runtime/:attempt_id/set_sco", post(rt_set_sco))
+        .layer(DefaultBodyLimit::max(runtime_limit))
+        .layer(middleware::map_response_with_state(
+            runtime_limit,
+            payload_too_large_json,
+        ));
     Router::new()
         // ingest + launch
+        .route("/api/courses", get(list_courses))
+        .route("/api/courses/:course_id", get(get_course))
         .route("/api/courses/upload", post(upload_course))
+        .route("/api/courses/upload_from_url", post(upload_course_from_url))
+        .route("/api/courses/import", post(import_course))
+        .route(
+            "/api/courses/:course_id/allowed_elements",
+            put(set_allowed_elements),
+        )
+        .route(
+            "/api/courses/:course_id/min_session_seconds",
+            put(set_min_session_seconds),
+        )
+        .route(
+            "/api/courses/:course_id/mastery_score",
+            put(set_mastery_score),
+        )
+        .route(
+            "/api/courses/:course_id/pass_threshold",
+            put(set_pass_threshold),
+        )
+        .route(
+            "/api/courses/:course_id/default_launch_strategy",
+            put(set_launch_strategy),
+        )
+        .route(
+            "/api/courses/:course_id/complete_on_score",
+            put(set_complete_on_score),
+        )
+        .route("/api/courses/:course_id/launch_mode", put(set_launch_mode))
+        .route(
+            "/api/courses/:course_id/max_attempts",
+            put(set_max_attempts),
+        )
+        .route("/api/courses/:course_id/recompute", post(recompute_course))
+        .route(
+            "/api/courses/:course_id/tags",
+            post(add_course_tags).delete(remove_course_tags),
+        )
+        .route("/api/courses/:course_id/thumbnail", get(course_thumbnail))
+        .route("/api/courses/:course_id/menu", get(course_menu))
+        .route("/api/courses/:course_id/package", get(course_package))
+        .route(
+            "/api/courses/:course_id/imsmanifest.xml",
+            get(course_manifest_xml),
+        )
+        .route("/api/courses/:course_id/files", get(course_files))
+        .route(
+            "/api/courses/:course_id/diagnostics",
+            get(course_diagnostics),
+        )
+        .route("/api/courses/:course_id/export.zip", get(course_export))
+        .route(
+            "/api/courses/:course_id/events.jsonl",
+            get(course_events_jsonl),
+        )
+        .route("/api/scos/:sco_id/comments_from_lms", put(set_sco_comments))
         .route("/api/attempts", post(create_attempt))
+        .route("/api/completions", get(learner_completion))
+        .route("/api/attempts/bulk", post(create_attempts_bulk))
+        .route("/api/registrations/:registration_id", get(get_registration))
+        .route(
+            "/api/attempts/:attempt_id",
+            get(get_attempt).patch(patch_attempt),
+        )
+        .route("/api/attempts/:attempt_id/grade", get(attempt_grade))
+        .route("/api/attempts/:attempt_id/next", get(next_sco))
+        .route("/api/attempts/:attempt_id/complete", post(complete_attempt))
+        .route("/api/attempts/:attempt_id/reopen", post(reopen_attempt))
+        .route("/api/attempts/:attempt_id/reassign", post(reassign_attempt))
+        .route("/api/attempts/:attempt_id/events", get(attempt_events))
         .route("/player/:attempt_id", get(player_shell))
+        .route("/player/bridge.js", get(player_bridge))
         // runtime API
-        .route("/runtime/:attempt_id/initialize", post(rt_initialize))
-        .route("/runtime/:attempt_id/set", post(rt_set))
-        .route("/runtime/:attempt_id/get", post(rt_get))
-        .route("/runtime/:attempt_id/commit", post(rt_commit))
-        .route("/runtime/:attempt_id/finish", post(rt_finish))
+        .merge(runtime)
         // static content (serves extracted course files)
-        .nest_service("/content", ServeDir::new(static_dir))
-        .with_state(db)
+        .nest_service("/content", content)
+        .with_state(state)
+}
+
+/// 404 for a missing `/content` asset: logged with the course base_path so broken
+/// packages show up in the server log, and a small page instead of an empty frame.
+async fn content_not_found(uri: axum::http::Uri) -> Html<String> {
+    let path = uri.path();
+    // served paths are `/courses/<id>/...`; the first two segments are the base_path
+    let base_path = path
+        .trim_start_matches('/')
+        .splitn(3, '/')
+        .take(2)
+        .collect::<Vec<_>>()
+        .join("/");
+    tracing::warn!(path = %path, base_path = %base_path, "content asset not found");
+    Html(format!(
+        "<!DOCTYPE html><html><head><meta charset='utf-8'/><title>Not found</title></head>\
+         <body><h1>Content not found</h1><p><code>{}</code> is not part of this package.</p></body></html>",
+        util::html_escape(path)
+    ))
+}
+
+/// Label charset-less HTML/CSS/JS as UTF-8; ServeDir only sends the bare mime type.
+async fn default_utf8_charset(mut res: Response) -> Response {
+    let Some(ct) = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return res;
+    };
+    let textual = matches!(
+        ct.split(';').next().unwrap_or("").trim(),
+        "text/html" | "text/css" | "text/javascript" | "application/javascript"
+    );
+    if textual && !ct.to_ascii_lowercase().contains("charset=") {
+        if let Ok(v) = HeaderValue::from_str(&format!("{}; charset=utf-8", ct)) {
+            res.headers_mut().insert(header::CONTENT_TYPE, v);
+        }
+    }
+    res
 }
 
 async fn upload_course(
-    State(db): State<Db>,
+    State(state): State<AppState>,
     mut mp: Multipart,
 ) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
     let mut title = None;
+    let mut organization = None;
     let mut zip_bytes: Option<Vec<u8>> = None;
 
-    while let Some(field) = mp.next_field().await.map_err(e500)? {
+    while let Some(field) = mp.next_field().await.map_err(multipart_err)? {
         let name = field.name().unwrap_or("").to_string();
         if name == "title" {
-            title = Some(field.text().await.map_err(e500)?);
+            title = Some(field.text().await.map_err(multipart_err)?);
+        } else if name == "organization_identifier" {
+            organization =
+                Some(field.text().await.map_err(multipart_err)?).filter(|o| !o.trim().is_empty());
         } else if name == "file" {
-            zip_bytes = Some(field.bytes().await.map_err(e500)?.to_vec());
+            zip_bytes = Some(field.bytes().await.map_err(multipart_err)?.to_vec());
         }
     }
 
-    let title = title.unwrap_or_else(|| "Untitled Course".into());
+    let title = title.filter(|t| !t.trim().is_empty());
     let bytes = zip_bytes.ok_or(e400("file is required"))?;
+    ingest_package(&state, title.as_deref(), organization.as_deref(), bytes)
+        .await
+        .map(Json)
+}
+
+/// Upload a package that already lives at a URL (e.g. object storage). The
+/// download is SSRF-guarded: private addresses are refused unless the host is
+/// in UPLOAD_URL_ALLOWLIST.
+async fn upload_course_from_url(
+    State(state): State<AppState>,
+    Json(req): Json<UploadFromUrlReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    let config = &state.config;
+    let bytes = fetch::fetch_package(
+        &req.url,
+        config.max_upload_bytes,
+        &config.upload_url_allowlist,
+    )
+    .await
+    .map_err(|err| {
+        let status = match err {
+            fetch::FetchError::Invalid(_) => StatusCode::BAD_REQUEST,
+            fetch::FetchError::Blocked(_) => StatusCode::FORBIDDEN,
+            fetch::FetchError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            fetch::FetchError::Upstream(_) => StatusCode::BAD_GATEWAY,
+        };
+        (status, err.to_string())
+    })?;
+    let title = req.title.filter(|t| !t.trim().is_empty());
+    let organization = req.organization_identifier.filter(|o| !o.trim().is_empty());
+    ingest_package(&state, title.as_deref(), organization.as_deref(), bytes)
+        .await
+        .map(Json)
+}
 
-    let base_dir = PathBuf::from(std::env::var("DATA_DIR").unwrap_or("./data".into()));
+/// Extract, parse and persist an uploaded package; shared by the multipart and
+/// URL uploads. The extracted tree is removed again when the package is rejected.
+/// Without an uploader `title`, the manifest's LOM title (else "Untitled Course") is used.
+async fn ingest_package(
+    state: &AppState,
+    title: Option<&str>,
+    organization: Option<&str>,
+    bytes: Vec<u8>,
+) -> Result<Course, (axum::http::StatusCode, String)> {
+    let base_dir = &state.config.data_dir;
     let course_id = Uuid::new_v4();
     let rel_base = format!("courses/{}", course_id);
     let out_dir = base_dir.join(&rel_base);
 
-    manifest::extract_zip_to_dir(&bytes, &out_dir).map_err(e500)?;
-    let mf = manifest::find_manifest(&out_dir).map_err(|_| e400("imsmanifest.xml not found"))?;
-    let parsed = manifest::parse_manifest(&mf).map_err(|_| e400("failed to parse manifest"))?;
-
-    // Persist course
-    let course = query_as!(Course,
-        r#"
-        INSERT INTO courses (id, title, org_identifier, launch_href, base_path)
-        VALUES ($1,$2,$3,$4,$5)
-        RETURNING id, title, org_identifier, launch_href, base_path, created_at
-        "#,
-        course_id, title, Option::<String>::None, parsed.default_launch, rel_base
-    )
-    .fetch_one(&db)
-    .await
-    .map_err(e500)?;
+    if bytes.is_empty() {
+        return Err(e400("package contains no files"));
+    }
+    // every rejection below removes what this upload wrote
+    let mut cleanup = IngestCleanup::new(out_dir.clone());
+    let opts = manifest::ExtractOptions {
+        symlinks: state.config.zip_symlinks,
+        case_collisions: state.config.zip_case_collisions,
+        overwrite: false,
+        max_path_depth: state.config.zip_max_path_depth,
+        max_path_len: state.config.zip_max_path_len,
+    };
+    let files = match manifest::extract_zip_to_dir(&bytes, &out_dir, opts) {
+        Ok(files) => files,
+        // another upload owns that tree; leave it alone
+        Err(err @ manifest::ExtractErr::TargetNotEmpty(_)) => {
+            cleanup.disarm();
+            tracing::warn!("course {}: {}", course_id, err);
+            return Err((
+                axum::http::StatusCode::CONFLICT,
+                "course directory is already in use; retry the upload".into(),
+            ));
+        }
+        Err(err) if err.is_unsafe_entry() => return Err(e400(err.to_string())),
+        Err(err) => return Err(e500(err)),
+    };
+    if files == 0 {
+        // empty or directory-only archive
+        return Err(e400("package contains no files"));
+    }
+    if state.config.transcode_html {
+        let n = manifest::transcode_legacy_html(&out_dir).map_err(e500)?;
+        if n > 0 {
+            tracing::debug!(
+                "transcoded {} legacy-encoded HTML files to UTF-8 in course {}",
+                n,
+                course_id
+            );
+        }
+    }
+    if state.config.strip_bom_assets {
+        let n = manifest::strip_text_boms(&out_dir).map_err(e500)?;
+        if n > 0 {
+            tracing::debug!(
+                "stripped UTF-8 BOM from {} files in course {}",
+                n,
+                course_id
+            );
+        }
+    }
+    let mut parsed = match manifest::find_manifest(&out_dir) {
+        Ok(mf) => match manifest::parse_manifest(&mf, state.config.max_manifest_bytes) {
+            Ok(parsed) => parsed,
+            Err(err) => return Err(e400(err.to_string())),
+        },
+        // opt-in: plain HTML zips launch their index page as a single SCO
+        Err(_) if state.config.allow_no_manifest => manifest::fallback_manifest(&out_dir)
+            .ok_or_else(|| e400("imsmanifest.xml not found and no index.html to fall back to"))?,
+        Err(_) => return Err(e400("imsmanifest.xml not found")),
+    };
 
-    // Persist SCOs
-    for (ident, href, params) in parsed.scos {
-        let _ = query!(
-            r#"INSERT INTO scos (course_id, identifier, launch_href, parameters) VALUES ($1,$2,$3,$4)"#,
-            course.id, ident, href, params
-        )
-        .execute(&db)
+    if parsed.scorm_version == manifest::ScormVersion::Scorm2004 && !state.config.enable_scorm_2004
+    {
+        return Err(e400("SCORM 2004 not supported in this deployment"));
+    }
+    // operator-pinned organization overrides the manifest's `default`
+    if let Some(org) = organization.map(str::trim) {
+        if !parsed.pin_organization(org) {
+            return Err(e400(format!("organization not found in manifest: {}", org)));
+        }
+    }
+    if state.config.strict_href {
+        if let Some(href) = parsed.external_launch() {
+            return Err(e400(format!(
+                "launch href is an absolute URL (STRICT_HREF): {}",
+                href
+            )));
+        }
+    }
+    if state.config.verify_launch_on_upload {
+        manifest::verify_launch_file(&out_dir, &parsed.default_launch).map_err(e400)?;
+    }
+
+    // last, so the rewrites above never write through a shared blob
+    if state.config.dedup_files {
+        let n = manifest::dedup_into_blobs(&out_dir, &state.config.blob_dir).map_err(e500)?;
+        tracing::debug!(
+            "{} of course {}'s files were already in the blob store",
+            n,
+            course_id
+        );
+    }
+
+    // keep the original zip (outside /content) for re-download
+    let pkg_path = package_path(&state.config.package_dir, course_id);
+    cleanup.package = Some(pkg_path.clone());
+    if let Some(dir) = pkg_path.parent() {
+        tokio::fs::create_dir_all(dir).await.map_err(e500)?;
+    }
+    tokio::fs::write(&pkg_path, &bytes).await.map_err(e500)?;
+
+    let title = title
+        .or(parsed.lom.title.as_deref())
+        .unwrap_or("Untitled Course")
+        .to_string();
+    let course = state
+        .courses
+        .insert_course(course_id, &title, &rel_base, parsed)
         .await
         .map_err(e500)?;
-    }
+    cleanup.disarm();
 
-    Ok(Json(course))
+    Ok(course)
 }
 
-async fn create_attempt(
-    State(db): State<Db>,
-    Json(req): Json<CreateAttemptReq>,
-) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
-    let course: Option<Course> =
-        query_as!(Course, "SELECT * FROM courses WHERE id=$1", req.course_id)
-            .fetch_optional(&db)
-            .await
-            .map_err(e500)?;
-    if course.is_none() {
-        return Err(e400("course not found"));
-    }
-
-    let attempt_id = Uuid::new_v4();
-    let rec = query_as!(Attempt,
-        r#"
-        INSERT INTO attempts (id, course_id, learner_id, sco_id, status, started_at)
-        VALUES ($1,$2,$3,$4,'in_progress', now())
-        RETURNING id, course_id, learner_id, sco_id, status, started_at, finished_at, created_at
-        "#,
-        attempt_id, req.course_id, req.learner_id, req.sco_id
-    )
-    .fetch_one(&db)
-    .await
-    .map_err(e500)?;
-
-    Ok(Json(rec))
+/// Removes an upload's extracted tree (and saved package, once written) on drop
+/// unless disarmed, so every early return from `ingest_package` cleans up.
+struct IngestCleanup {
+    dir: std::path::PathBuf,
+    package: Option<std::path::PathBuf>,
+    armed: bool,
 }
 
-async fn player_shell(
-    State(db): State<Db>,
-    Path(attempt_id): Path<Uuid>,
-) -> Result<Html<String>, (axum::http::StatusCode, String)> {
-    let attempt: Attempt =
-        query_as!(Attempt, "SELECT * FROM attempts WHERE id=$1", attempt_id)
-            .fetch_one(&db)
-            .await
-            .map_err(e500)?;
-    let course: Course =
-        query_as!(Course, "SELECT * FROM courses WHERE id=$1", attempt.course_id)
-            .fetch_one(&db)
-            .await
-            .map_err(e500)?;
+impl IngestCleanup {
+    fn new(dir: std::path::PathBuf) -> Self {
+        IngestCleanup {
+            dir,
+            package: None,
+            armed: true,
+        }
+    }
 
-    // Decide which href to launch
-    let href = if let Some(sco_id) = attempt.sco_id {
-        let sco: Sco = query_as!(Sco, "SELECT * FROM scos WHERE id=$1", sco_id)
-            .fetch_one(&db)
-            .await
-            .map_err(e500)?;
-        sco.launch_href
-    } else {
-        course.launch_href.clone()
-    };
-
-    // ServeDir is mounted at /content; base_path is relative to DATA_DIR
-    let launch_url = format!("/content/{}/{}", course.base_path, href);
-
-    let html = format!(
-    r#"<!DOCTYPE html>
-<html>
-<head>
-  <meta charset='utf-8'/>
-  <title>SCORM Player</title>
-  <meta http-equiv="Content-Security-Policy"
-        content="default-src 'self'; script-src 'self' 'unsafe-inline'; style-src 'self' 'unsafe-inline'; img-src 'self' data: blob:; media-src 'self' blob:; font-src 'self' data:; frame-src 'self'; connect-src 'self';" />
-  <style>
-    html,body,iframe{{height:100%;width:100%;margin:0;padding:0;border:0}}
-    .bar{{position:fixed;top:0;left:0;right:0;height:36px;background:#eee;border-bottom:1px solid #ddd;display:flex;align-items:center;padding:0 8px;z-index:2}}
-    iframe{{position:absolute;top:36px;left:0;right:0;bottom:0}}
-  </style>
-</head>
-<body>
-<div class='bar'>Attempt {attempt_id} • <button onclick="console.log(window.APICommit())">Commit</button> <span id='status'></span></div>
-<iframe id='sco' src='{launch_url}'></iframe>
-<script>
-(function(){{ 
-  const cache = {{}};
-  const attemptId = '{attempt_id}';
-
-  async function post(path, body){{ 
-    const res = await fetch(`/runtime/${{attemptId}}/${{path}}`, {{
-      method:'POST',
-      headers:{{'content-type':'application/json'}},
-      body: JSON.stringify(body||{{}})
-    }});
-    const j = await res.json().catch(()=>({{}}));
-    return j;
-  }}
-
-  async function initializeFromServer(){{ 
-    try {{
-      const j = await post('initialize');
-      if (j && j.values && typeof j.values === 'object') {{
-        Object.assign(cache, j.values);
-      }}
-    }} catch(e){{ console.warn('init failed', e); }}
-  }}
-
-  // SCORM 1.2 API shim
-  window.API = {{
-    LMSInitialize(arg){{ return "true"; }},
-    LMSFinish(arg){{ post('finish'); return "true"; }},
-    LMSGetValue(el){{ return (el in cache) ? String(cache[el]) : ""; }},
-    LMSSetValue(el, v){{ cache[el]=String(v); return "true"; }},
-    LMSCommit(arg){{ 
-      post('commit', cache).then(()=>{{
-        const s = document.getElementById('status');
-        if (s){{ s.textContent='saved'; setTimeout(()=> s.textContent='', 1200); }}
-      }});
-      return "true";
-    }},
-    LMSGetLastError(){{ return "0"; }},
-    LMSGetErrorString(c){{ return "No error"; }},
-    LMSGetDiagnostic(c){{ return ""; }}
-  }};
-
-  // Seed cache before the SCO loads too far
-  initializeFromServer();
-
-  // toolbar helper
-  window.APICommit = ()=> window.API.LMSCommit("");
-}})();
-</script>
-</body>
-</html>"#,
-    attempt_id = attempt_id,
-    launch_url = launch_url
-);
+    fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
 
-    Ok(Html(html))
+impl Drop for IngestCleanup {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+        if let Some(package) = &self.package {
+            let _ = std::fs::remove_file(package);
+        }
+    }
 }
 
-// --- Runtime endpoints (MVP) ---
+fn package_path(package_dir: &std::path::Path, course_id: Uuid) -> std::path::PathBuf {
+    package_dir.join(format!("{}.zip", course_id))
+}
 
-async fn rt_initialize(
-    State(db): State<Db>,
-    Path(attempt_id): Path<Uuid>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let rows = sqlx::query!(
-        r#"SELECT element, value FROM cmi_values WHERE attempt_id = $1"#,
-        attempt_id
+/// Original upload, named after the course title.
+async fn course_package(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    // imported courses never had a package
+    let bytes = tokio::fs::read(package_path(&state.config.package_dir, course_id))
+        .await
+        .map_err(|_| e404("original package not available"))?;
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        util::package_filename(&course.title)
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
     )
-    .fetch_all(&db)
-    .await
-    .map_err(e500)?;
+        .into_response())
+}
 
-    let mut map = serde_json::Map::new();
-    for r in rows {
-        // element is NOT NULL in schema; value may be NULL
-        let v = r.value.unwrap_or_default();
-        map.insert(r.element, serde_json::Value::String(v));
+/// The extracted course re-packed as a SCORM zip (manifest included), e.g. for
+/// migrating to another LMS. Built in a temp file, then streamed from it.
+async fn course_export(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    request: axum::extract::Request,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let dir = state.config.data_dir.join(&course.base_path);
+    if !dir.is_dir() {
+        return Err(e404("course files not found"));
+    }
+    let tmp = std::env::temp_dir().join(format!("export-{}.zip", Uuid::new_v4()));
+    let out = tmp.clone();
+    let zipped = tokio::task::spawn_blocking(move || manifest::zip_dir(&dir, &out))
+        .await
+        .map_err(e500)?;
+    if let Err(e) = zipped {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e500(e));
     }
 
-    Ok(Json(serde_json::json!({ "values": map })))
+    let zip_mime: mime_guess::Mime = "application/zip".parse().map_err(e500)?;
+    let res = ServeFile::new_with_mime(&tmp, &zip_mime)
+        .oneshot(request)
+        .await;
+    // the response body holds the open file; the name is no longer needed
+    let _ = std::fs::remove_file(&tmp);
+    let mut res = res.map_err(e500)?.into_response();
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        util::package_filename(&course.title)
+    );
+    res.headers_mut().insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&disposition).map_err(e500)?,
+    );
+    Ok(res)
 }
-async fn rt_set() -> impl IntoResponse {
-    Json(serde_json::json!({ "ok": true }))
+
+/// The package's `imsmanifest.xml` as stored on disk, for support and integrators.
+async fn course_manifest_xml(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    request: axum::extract::Request,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let path = manifest::find_manifest(&state.config.data_dir.join(&course.base_path))
+        .map_err(|_| e404("imsmanifest.xml not found"))?;
+    let xml: mime_guess::Mime = "application/xml".parse().map_err(e500)?;
+    let res = ServeFile::new_with_mime(path, &xml)
+        .oneshot(request)
+        .await
+        .map_err(e500)?;
+    Ok(res.into_response())
 }
-async fn rt_get() -> impl IntoResponse {
-    Json(serde_json::json!({ "value": "" }))
+
+/// Declared files per manifest resource (dependencies expanded), with the items
+/// that launch each one; e.g. for CDN warming. Re-parses the stored manifest.
+async fn course_files(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let path = manifest::find_manifest(&state.config.data_dir.join(&course.base_path))
+        .map_err(|_| e404("imsmanifest.xml not found"))?;
+    let parsed = manifest::parse_manifest(&path, state.config.max_manifest_bytes).map_err(e500)?;
+
+    let resources: Vec<_> = parsed
+        .resources
+        .iter()
+        .map(|r| {
+            let scos: Vec<&str> = parsed
+                .scos
+                .iter()
+                .filter(|s| s.resource.as_deref() == Some(r.identifier.as_str()))
+                .map(|s| s.identifier.as_str())
+                .collect();
+            serde_json::json!({
+                "identifier": r.identifier,
+                "href": r.href,
+                "scos": scos,
+                "files": r.files,
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({ "resources": resources })))
 }
 
-async fn rt_commit(
-    State(db): State<Db>,
-    Path(attempt_id): Path<Uuid>,
-    Json(map): Json<serde_json::Value>,
+/// Ingest details for troubleshooting a course: what upload recorded, plus a fresh
+/// parse of the stored manifest (warnings, files it references that are missing).
+async fn course_diagnostics(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
 ) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
-    let obj = map.as_object().cloned().unwrap_or_default();
-    for (el, val) in obj.iter() {
-    // Make an owned String so we never borrow a temporary.
-        let value: String = val
-            .as_str()
-            .map(|s| s.to_owned())
-            .unwrap_or_else(|| val.to_string());
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let scos = state
+        .courses
+        .scos_for_course(course_id)
+        .await
+        .map_err(e500)?;
+    let course_dir = state.config.data_dir.join(&course.base_path);
+    let candidates: Vec<_> = course
+        .launch_candidates
+        .iter()
+        .map(|href| {
+            let exists =
+                manifest::first_existing_href(&course_dir, std::slice::from_ref(href)).is_some();
+            serde_json::json!({ "href": href, "exists": exists })
+        })
+        .collect();
 
-        if !runtime::is_valid_element_12(el) {
-            continue;
+    let mut body = serde_json::json!({
+        "scorm_version": course.scorm_version,
+        "default_launch_strategy": course.default_launch_strategy,
+        "launch_href": course.launch_href,
+        "launch_candidates": candidates,
+        "sco_count": scos.len(),
+        "manifest": null,
+        "warnings": [],
+        "missing_files": [],
+    });
+    // imported and ALLOW_NO_MANIFEST courses have no manifest to re-check
+    if let Ok(path) = manifest::find_manifest(&course_dir) {
+        body["manifest"] = path
+            .strip_prefix(&course_dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .into();
+        match manifest::parse_manifest(&path, state.config.max_manifest_bytes) {
+            Ok(parsed) => {
+                let root = path.parent().unwrap_or(&course_dir);
+                body["missing_files"] = manifest::verify_resources(root, &parsed).into();
+                body["warnings"] = parsed.warnings.into();
+            }
+            Err(e) => {
+                body["warnings"] = serde_json::json!([format!("manifest no longer parses: {}", e)])
+            }
         }
-        if value.len() > runtime::max_len(el) {
-            continue;
+    }
+    Ok(Json(body))
+}
+
+async fn import_course(
+    State(state): State<AppState>,
+    Json(req): Json<ImportCourseReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    let rel_base = req.base_path.trim_matches('/').to_string();
+    if rel_base.is_empty() || !util::is_safe_relative_path(&rel_base) {
+        return Err(e400("base_path must be a relative path inside DATA_DIR"));
+    }
+    let base_dir = &state.config.data_dir;
+    let course_dir = base_dir.join(&rel_base);
+    if !course_dir.is_dir() {
+        return Err(e400(format!("base_path not found: {}", rel_base)));
+    }
+
+    // every referenced launch file must already be present (content is synced out-of-band)
+    let hrefs = std::iter::once(&req.launch_href).chain(req.scos.iter().map(|s| &s.launch_href));
+    for href in hrefs {
+        if !util::is_safe_relative_path(href)
+            || manifest::first_existing_href(&course_dir, std::slice::from_ref(href)).is_none()
+        {
+            return Err(e400(format!("launch file not found: {}", href)));
         }
+    }
 
-        let v_final = if *el == "cmi.core.lesson_status" {
-            runtime::normalize_lesson_status(&value)
-                .unwrap_or("incomplete")
-                .to_string()
-        } else {
-            value.clone()
-        };
+    let mut launch_candidates = vec![req.launch_href.clone()];
+    if let Some(first) = req.scos.first() {
+        if first.launch_href != req.launch_href {
+            launch_candidates.push(first.launch_href.clone());
+        }
+    }
+    let scos: Vec<manifest::ParsedSco> = req
+        .scos
+        .into_iter()
+        .map(|s| manifest::ParsedSco {
+            identifier: s.identifier,
+            href: s.launch_href,
+            parameters: s.parameters,
+            control_mode: manifest::ControlMode::default(),
+            visible: true,
+            prerequisites: None,
+            resource: None,
+            mobile_href: None,
+            launch_data: None,
+        })
+        .collect();
 
-        let _ = query!(
-            r#"
-            INSERT INTO cmi_values (attempt_id, element, value)
-            VALUES ($1,$2,$3)
-            ON CONFLICT (attempt_id, element)
-            DO UPDATE SET value=EXCLUDED.value, updated_at=now()
-            "#,
-            attempt_id,
-            el,
-            v_final
-        )
-        .execute(&db)
+    let parsed = manifest::ParsedManifest {
+        default_launch: req.launch_href,
+        launch_candidates,
+        scos,
+        scorm_version: manifest::ScormVersion::default(),
+        thumbnail_href: None,
+        organizations: Vec::new(),
+        org_identifier: None,
+        mastery_score: None,
+        resources: Vec::new(),
+        warnings: Vec::new(),
+        lom: manifest::LomGeneral::default(),
+    };
+    let course = state
+        .courses
+        .insert_course(Uuid::new_v4(), &req.title, &rel_base, parsed)
         .await
         .map_err(e500)?;
+
+    Ok(Json(course))
+}
+
+/// Admin: restrict (or with `null`, unrestrict) which elements a course may write.
+async fn set_allowed_elements(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<AllowedElementsReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let allowed = req.allowed_elements.map(serde_json::Value::from);
+    let course = state
+        .courses
+        .update_course(course_id, CourseSetting::AllowedElements(allowed))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: minimum accumulated time before a completed/passed status is accepted.
+async fn set_min_session_seconds(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<MinSessionReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    if req.min_session_seconds.is_some_and(|s| s < 0) {
+        return Err(e400("min_session_seconds must not be negative"));
     }
- 
-    // Check completion status (deal with Option<Option<String>> from query_scalar+optional+nullable)
-    let status: Option<String> = sqlx::query_scalar!(
-        "SELECT value FROM cmi_values WHERE attempt_id=$1 AND element='cmi.core.lesson_status'",
-        attempt_id
-    )
-    .fetch_optional(&db)
-    .await
-    .map_err(e500)?
-    .flatten();
-
-    if let Some(status) = status {
-        if matches!(status.as_str(), "completed" | "passed" | "failed") {
-            let _ = query!(
-                "UPDATE attempts SET status='completed', finished_at=now() WHERE id=$1",
-                attempt_id
-            )
-            .execute(&db)
-            .await
-            .map_err(e500)?;
-        }
+    let course = state
+        .courses
+        .update_course(
+            course_id,
+            CourseSetting::MinSessionSeconds(req.min_session_seconds),
+        )
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: cap how many attempts one learner may start on the course.
+async fn set_max_attempts(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<MaxAttemptsReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    if req.max_attempts.is_some_and(|m| m < 1) {
+        return Err(e400("max_attempts must be at least 1"));
     }
+    let course = state
+        .courses
+        .update_course(course_id, CourseSetting::MaxAttempts(req.max_attempts))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
 
-    Ok(Json(serde_json::json!({ "ok": true })))
+/// Admin: SCO-specific feedback delivered to that SCO as `cmi.comments_from_lms`.
+async fn set_sco_comments(
+    State(state): State<AppState>,
+    Path(sco_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<ScoCommentsReq>,
+) -> Result<Json<Sco>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    if req
+        .comments_from_lms
+        .as_ref()
+        .is_some_and(|c| c.len() > runtime::max_len("cmi.comments_from_lms"))
+    {
+        return Err(e400("comments_from_lms is too long"));
+    }
+    let sco = state
+        .courses
+        .set_sco_comments(sco_id, req.comments_from_lms.as_deref())
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("sco not found"))?;
+    Ok(Json(sco))
 }
 
-async fn rt_finish(
-    State(db): State<Db>,
-    Path(attempt_id): Path<Uuid>,
+/// Admin: 1.2 mastery score; finished attempts with a raw score become passed/failed.
+async fn set_mastery_score(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<MasteryScoreReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    if req
+        .mastery_score
+        .is_some_and(|m| !(0.0..=100.0).contains(&m))
+    {
+        return Err(e400("mastery_score must be between 0 and 100"));
+    }
+    let course = state
+        .courses
+        .update_course(course_id, CourseSetting::MasteryScore(req.mastery_score))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: pass bar that overrides the mastery score without re-authoring the package.
+async fn set_pass_threshold(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<PassThresholdReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    if req
+        .pass_threshold
+        .is_some_and(|t| !(0.0..=100.0).contains(&t))
+    {
+        return Err(e400("pass_threshold must be between 0 and 100"));
+    }
+    let course = state
+        .courses
+        .update_course(course_id, CourseSetting::PassThreshold(req.pass_threshold))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: how the player picks a SCO when an attempt names none.
+async fn set_launch_strategy(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<LaunchStrategyReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let strategy = req
+        .default_launch_strategy
+        .trim()
+        .parse::<LaunchStrategy>()
+        .map_err(|_| {
+            e400(format!(
+                "default_launch_strategy must be one of: {}",
+                LaunchStrategy::ALL.map(|s| s.as_str()).join(", ")
+            ))
+        })?;
+    let course = state
+        .courses
+        .update_course(course_id, CourseSetting::LaunchStrategy(strategy))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: run the course's SCOs in the player iframe or in a popup window.
+async fn set_launch_mode(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<LaunchModeReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let mode = req.launch_mode.trim().parse::<LaunchMode>().map_err(|_| {
+        e400(format!(
+            "launch_mode must be one of: {}",
+            LaunchMode::ALL.map(|m| m.as_str()).join(", ")
+        ))
+    })?;
+    let course = state
+        .courses
+        .update_course(course_id, CourseSetting::LaunchMode(mode))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: let a committed raw score complete attempts whose SCO never sets a status.
+async fn set_complete_on_score(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<CompleteOnScoreReq>,
+) -> Result<Json<Course>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let course = state
+        .courses
+        .update_course(
+            course_id,
+            CourseSetting::CompleteOnScore(req.complete_on_score),
+        )
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    Ok(Json(course))
+}
+
+/// Admin: re-run the lesson_status derivation over every attempt of the course
+/// (e.g. after changing the mastery score or pass threshold). Returns how many attempts changed.
+async fn recompute_course(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
-    let _ = query!(
-        "UPDATE attempts SET status='completed', finished_at=now() WHERE id=$1",
-        attempt_id
-    )
-    .execute(&db)
-    .await
+    require_admin(&state.config, &headers)?;
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+
+    let passing_score = course.passing_score();
+    let complete_on_score = course.complete_on_score;
+    let derive = move |attempt: &Attempt, values: &[CmiValue]| {
+        let get = |el: &str| {
+            values
+                .iter()
+                .find(|v| v.element == el)
+                .and_then(|v| v.value.as_deref())
+        };
+        let (status, rewritten) = derived_lesson_status(
+            get("cmi.core.lesson_status"),
+            get("cmi.core.score.raw"),
+            passing_score,
+            complete_on_score,
+        );
+        let finished = status.as_deref().and_then(runtime::finished_status);
+        // an open attempt is closed out and a completed/failed one follows its new
+        // outcome; timed_out, suspended and expired stay as recorded
+        let current = attempt.status.parse::<AttemptStatus>().ok();
+        let movable = current.is_some_and(|s| s == AttemptStatus::InProgress || s.is_completed());
+        Rederived {
+            lesson_status: status.filter(|_| rewritten),
+            status: finished.filter(|f| movable && current != Some(*f)),
+        }
+    };
+    let audit = state.config.audits("cmi.core.lesson_status");
+    let changed = state
+        .attempts
+        .rederive_course(course_id, &derive, audit)
+        .await
+        .map_err(e500)?;
+
+    Ok(Json(serde_json::json!({ "changed": changed })))
+}
+
+#[derive(serde::Deserialize, Default)]
+struct ListCoursesParams {
+    // only courses carrying this tag (case-insensitive)
+    tag: Option<String>,
+}
+
+async fn list_courses(
+    State(state): State<AppState>,
+    Query(params): Query<ListCoursesParams>,
+    headers: HeaderMap,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let courses = match params.tag.as_deref().map(normalize_tag) {
+        Some(tag) => state.courses.courses_tagged(&tag).await,
+        None => state.courses.list_courses().await,
+    }
     .map_err(e500)?;
-    Ok(Json(serde_json::json!({ "ok": true })))
+    json_with_etag(&headers, &courses)
 }
 
-// --- helpers ---
-fn e400<T: Into<String>>(msg: T) -> (axum::http::StatusCode, String) {
-    (axum::http::StatusCode::BAD_REQUEST, msg.into())
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
 }
 
-fn e500<E: std::fmt::Display>(e: E) -> (axum::http::StatusCode, String) {
-    tracing::error!(error=%e, "internal error");
-    (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+/// Normalized, de-duplicated tags from a request; empty or oversized tags are a 400.
+fn request_tags(req: CourseTagsReq) -> Result<Vec<String>, (axum::http::StatusCode, String)> {
+    let mut tags: Vec<String> = Vec::with_capacity(req.tags.len());
+    for tag in req.tags.iter().map(|t| normalize_tag(t)) {
+        if tag.is_empty() || tag.len() > 64 {
+            return Err(e400("tags must be 1-64 characters"));
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(tags)
 }
 
+/// Admin: attach catalog tags to a course. Returns the course's full tag list.
+async fn add_course_tags(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<CourseTagsReq>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let tags = request_tags(req)?;
+    state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    state
+        .courses
+        .add_course_tags(course_id, &tags)
+        .await
+        .map_err(e500)?;
+    let tags = state.courses.course_tags(course_id).await.map_err(e500)?;
+    Ok(Json(
+        serde_json::json!({ "course_id": course_id, "tags": tags }),
+    ))
+}
+
+/// Admin: detach catalog tags; unknown tags are ignored.
+async fn remove_course_tags(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<CourseTagsReq>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let tags = request_tags(req)?;
+    state
+        .courses
+        .remove_course_tags(course_id, &tags)
+        .await
+        .map_err(e500)?;
+    let tags = state.courses.course_tags(course_id).await.map_err(e500)?;
+    Ok(Json(
+        serde_json::json!({ "course_id": course_id, "tags": tags }),
+    ))
+}
+
+async fn get_course(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    json_with_etag(&headers, &course)
+}
+
+/// Learner-facing table of contents: visible SCOs in manifest order.
+async fn course_menu(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let items: Vec<_> = state
+        .courses
+        .scos_for_course(course_id)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .filter(|s| s.is_visible)
+        .map(|s| {
+            serde_json::json!({
+                "sco_id": s.id,
+                "identifier": s.identifier,
+                "launch_url": sco_launch_url(&course, &s),
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({ "items": items })))
+}
+
+async fn course_thumbnail(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let href = course
+        .thumbnail_href
+        .filter(|h| util::is_safe_relative_path(h))
+        .ok_or_else(|| e404("course has no thumbnail"))?;
+
+    let base_dir = &state.config.data_dir;
+    let bytes = tokio::fs::read(base_dir.join(&course.base_path).join(&href))
+        .await
+        .map_err(|_| e404("thumbnail file missing"))?;
+    let mime = mime_guess::from_path(&href).first_or_octet_stream();
+    Ok(([(header::CONTENT_TYPE, mime.to_string())], bytes).into_response())
+}
+
+#[derive(serde::Deserialize, Default)]
+struct EventsExportParams {
+    // RFC 3339; only writes logged after this instant
+    since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Stream the course's cmi_value_log as JSON Lines, oldest first.
+async fn course_events_jsonl(
+    State(state): State<AppState>,
+    Path(course_id): Path<Uuid>,
+    Query(params): Query<EventsExportParams>,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    if state
+        .courses
+        .course(course_id)
+        .await
+        .map_err(e500)?
+        .is_none()
+    {
+        return Err(e404("course not found"));
+    }
+
+    let lines = state
+        .attempts
+        .value_log(course_id, params.since)
+        .map(|row| {
+            row.map(|r| {
+                let mut line = serde_json::json!({
+                    "id": r.id,
+                    "attempt_id": r.attempt_id,
+                    "learner_id": r.learner_id,
+                    "element": r.element,
+                    "value": r.value,
+                    "logged_at": r.logged_at,
+                })
+                .to_string();
+                line.push('\n');
+                line
+            })
+        });
+    let body = axum::body::Body::from_stream(lines);
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response())
+}
+
+/// Serialized size cap for `CreateAttemptReq::metadata`.
+const MAX_ATTEMPT_METADATA_BYTES: usize = 16 * 1024;
+
+async fn create_attempt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateAttemptReq>,
+) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
+    let course = state
+        .courses
+        .course(req.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e400("course not found"))?;
+    if let Some(loc) = &req.start_location {
+        if loc.len() > runtime::max_len("cmi.core.lesson_location") {
+            return Err(e400("start_location is too long"));
+        }
+    }
+    if req
+        .language
+        .as_ref()
+        .is_some_and(|l| l.len() > runtime::max_len(runtime::LANGUAGE_12))
+    {
+        return Err(e400("language is too long"));
+    }
+    if let Some(url) = &req.return_url {
+        let ok = url.len() <= 2048
+            && reqwest::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+        if !ok {
+            return Err(e400("return_url must be an absolute http(s) URL"));
+        }
+    }
+    if let Some(metadata) = &req.metadata {
+        if !metadata.is_object() {
+            return Err(e400("metadata must be a JSON object"));
+        }
+        if metadata.to_string().len() > MAX_ATTEMPT_METADATA_BYTES {
+            return Err(e400("metadata is too large"));
+        }
+    }
+    check_sco_in_course(&state, req.course_id, req.sco_id).await?;
+
+    // an existing registration reuses its attempt for the same SCO; otherwise start a new run
+    let (rec, created) = match req.registration_id {
+        Some(registration_id) => {
+            let reg = state
+                .attempts
+                .registration(registration_id)
+                .await
+                .map_err(e500)?
+                .ok_or_else(|| e400("registration not found"))?;
+            if reg.course_id != req.course_id || reg.learner_id != req.learner_id {
+                return Err(e400(
+                    "registration belongs to a different course or learner",
+                ));
+            }
+            let existing = state
+                .attempts
+                .registration_attempts(reg.id)
+                .await
+                .map_err(e500)?
+                .into_iter()
+                .find(|a| a.sco_id == req.sco_id);
+            match existing {
+                Some(a) => (a, false),
+                None => {
+                    check_attempt_limit(&state, &course, &req.learner_id, &headers).await?;
+                    let rec = state
+                        .attempts
+                        .create_attempt(&req, reg.id)
+                        .await
+                        .map_err(e500)?;
+                    (rec, true)
+                }
+            }
+        }
+        None => {
+            check_attempt_limit(&state, &course, &req.learner_id, &headers).await?;
+            let reg = state
+                .attempts
+                .create_registration(req.course_id, &req.learner_id, req.learner_name.as_deref())
+                .await
+                .map_err(e500)?;
+            let rec = state
+                .attempts
+                .create_attempt(&req, reg.id)
+                .await
+                .map_err(e500)?;
+            (rec, true)
+        }
+    };
+
+    // a reused attempt keeps the learner's own bookmark
+    if let Some(loc) = req.start_location.as_ref().filter(|_| created) {
+        for (el, v) in [
+            ("cmi.core.lesson_location", loc.as_str()),
+            ("cmi.core.entry", "resume"),
+        ] {
+            state
+                .attempts
+                .put_value(rec.id, el, v, false)
+                .await
+                .map_err(e500)?;
+        }
+    }
+
+    Ok(Json(rec))
+}
+
+/// 409 once the learner has `max_attempts` attempts on the course; a valid admin
+/// bearer token bypasses the cap.
+async fn check_attempt_limit(
+    state: &AppState,
+    course: &Course,
+    learner_id: &str,
+    headers: &HeaderMap,
+) -> Result<(), (axum::http::StatusCode, String)> {
+    let Some(max) = course.max_attempts else {
+        return Ok(());
+    };
+    if require_admin(&state.config, headers).is_ok() {
+        return Ok(());
+    }
+    let used = state
+        .attempts
+        .learner_attempts(course.id, learner_id)
+        .await
+        .map_err(e500)?
+        .len();
+    if used >= max as usize {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "learner {} has used all {} attempts for this course",
+                learner_id, max
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// A requested sco_id must exist and belong to the attempt's course.
+async fn check_sco_in_course(
+    state: &AppState,
+    course_id: Uuid,
+    sco_id: Option<Uuid>,
+) -> Result<(), (axum::http::StatusCode, String)> {
+    let Some(sco_id) = sco_id else { return Ok(()) };
+    match state.courses.sco(sco_id).await.map_err(e500)? {
+        Some(sco) if sco.course_id == course_id => Ok(()),
+        Some(_) => Err(e400("sco_id does not belong to course_id")),
+        None => Err(e400("sco not found")),
+    }
+}
+
+async fn create_attempts_bulk(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<BulkAttemptsReq>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    if req.learners.is_empty() {
+        return Err(e400("learners must not be empty"));
+    }
+    let mut seen = std::collections::HashSet::new();
+    for l in &req.learners {
+        if !seen.insert(l.learner_id.as_str()) {
+            return Err(e400(format!(
+                "duplicate learner_id in batch: {}",
+                l.learner_id
+            )));
+        }
+    }
+
+    let course = state
+        .courses
+        .course(req.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e400("course not found"))?;
+    check_sco_in_course(&state, req.course_id, req.sco_id).await?;
+    for l in &req.learners {
+        check_attempt_limit(&state, &course, &l.learner_id, &headers).await?;
+    }
+
+    // all-or-nothing: one failed insert rolls back the whole roster
+    let ids = state
+        .attempts
+        .create_roster(req.course_id, req.sco_id, &req.learners)
+        .await
+        .map_err(e500)?;
+    let created: serde_json::Map<_, _> = req
+        .learners
+        .into_iter()
+        .zip(ids)
+        .map(|(l, id)| (l.learner_id, serde_json::json!(id)))
+        .collect();
+
+    Ok(Json(serde_json::json!({ "attempts": created })))
+}
+
+/// The attempt row, including caller `metadata`.
+async fn get_attempt(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    Ok(Json(attempt))
+}
+
+/// Registration with its per-SCO attempts and a completion rollup across SCOs.
+async fn get_registration(
+    State(state): State<AppState>,
+    Path(registration_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let reg = state
+        .attempts
+        .registration(registration_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("registration not found"))?;
+    let attempts = state
+        .attempts
+        .registration_attempts(reg.id)
+        .await
+        .map_err(e500)?;
+    let course = state
+        .courses
+        .course(reg.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let scos = state
+        .courses
+        .scos_for_course(course.id)
+        .await
+        .map_err(e500)?;
+
+    let completed = completed_scos(&course, &attempts, &scos, &state.config.data_dir);
+    let status = runtime::registration_status(scos.len(), completed.len(), !attempts.is_empty());
+
+    let mut body = serde_json::to_value(&reg).map_err(e500)?;
+    body["status"] = status.into();
+    body["attempts"] = serde_json::to_value(&attempts).map_err(e500)?;
+    Ok(Json(body))
+}
+
+/// SCOs with a completed (or failed) attempt, each attempt counted on its
+/// `effective_sco`. `attempts` is the whole registration, oldest first.
+fn completed_scos(
+    course: &Course,
+    attempts: &[Attempt],
+    scos: &[Sco],
+    data_dir: &std::path::Path,
+) -> std::collections::HashSet<Uuid> {
+    attempts
+        .iter()
+        .filter(|a| attempt_completed(a))
+        .filter_map(|a| effective_sco(a, course, scos, attempts, data_dir))
+        .map(|s| s.id)
+        .collect()
+}
+
+/// `AttemptStatus::is_completed` for a stored status string.
+fn attempt_completed(attempt: &Attempt) -> bool {
+    attempt
+        .status
+        .parse::<AttemptStatus>()
+        .is_ok_and(|s| s.is_completed())
+}
+
+/// Guided navigation: the first visible SCO (manifest order) in the attempt's
+/// registration that is not completed and whose prerequisites are met.
+async fn next_sco(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    let course = state
+        .courses
+        .course(attempt.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let attempts = state
+        .attempts
+        .registration_attempts(attempt.registration_id)
+        .await
+        .map_err(e500)?;
+    let scos = state
+        .courses
+        .scos_for_course(course.id)
+        .await
+        .map_err(e500)?;
+
+    let completed = completed_scos(&course, &attempts, &scos, &state.config.data_dir);
+    let done = |identifier: &str| {
+        // identifiers that name no SCO (e.g. aggregations) don't block
+        scos.iter()
+            .find(|s| s.identifier == identifier)
+            .is_none_or(|s| completed.contains(&s.id))
+    };
+    let remaining: Vec<&Sco> = scos
+        .iter()
+        .filter(|s| s.is_visible && !completed.contains(&s.id))
+        .collect();
+    // unsupported prerequisite syntax is treated as met rather than locking the learner out
+    let next = remaining.iter().find(|s| {
+        s.prerequisites
+            .as_deref()
+            .is_none_or(|p| runtime::prerequisites_met(p, &done).unwrap_or(true))
+    });
+
+    let next = next.map(|s| {
+        serde_json::json!({
+            "sco_id": s.id,
+            "identifier": s.identifier,
+            "launch_url": sco_launch_url(&course, s),
+        })
+    });
+    Ok(Json(
+        serde_json::json!({ "complete": remaining.is_empty(), "next": next }),
+    ))
+}
+
+#[derive(serde::Deserialize)]
+struct CompletionParams {
+    course_id: Uuid,
+    learner_id: String,
+}
+
+/// Gradebook summary across a learner's attempts on a course: completed once any
+/// attempt completed, with the best lesson_status and raw score seen.
+async fn learner_completion(
+    State(state): State<AppState>,
+    Query(params): Query<CompletionParams>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    state
+        .courses
+        .course(params.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let attempts = state
+        .attempts
+        .learner_attempts(params.course_id, &params.learner_id)
+        .await
+        .map_err(e500)?;
+    let mut rows = Vec::with_capacity(attempts.len());
+    for a in attempts {
+        let lesson_status = state
+            .attempts
+            .value(a.id, "cmi.core.lesson_status")
+            .await
+            .map_err(e500)?;
+        rows.push((a, lesson_status));
+    }
+
+    let completed: Vec<_> = rows
+        .iter()
+        .map(|(a, _)| a)
+        .filter(|a| attempt_completed(a))
+        .collect();
+    let completed_at = completed.iter().filter_map(|a| a.finished_at).min();
+    let best_status = rows
+        .iter()
+        .filter_map(|(_, s)| s.as_deref())
+        .max_by_key(|s| runtime::lesson_status_rank(s));
+    let best_score = rows
+        .iter()
+        .filter_map(|(a, _)| a.score_raw)
+        .reduce(f64::max);
+    let unified = runtime::unify_status(best_status, state.config.browsed_completes);
+
+    Ok(Json(serde_json::json!({
+        "completed": !completed.is_empty(),
+        "best_status": best_status,
+        "completion": unified.completion,
+        "success": unified.success,
+        "best_score": best_score,
+        "completed_at": completed_at,
+    })))
+}
+
+/// Compact status/score plus caller metadata for host LMS polling; honors
+/// If-Modified-Since with 304.
+async fn attempt_grade(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    let rows = state.attempts.values(attempt_id).await.map_err(e500)?;
+    let updated_at = [
+        Some(attempt.created_at),
+        attempt.started_at,
+        attempt.finished_at,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(rows.iter().map(|r| r.updated_at))
+    .max()
+    .unwrap_or(attempt.created_at);
+
+    // HTTP dates have second resolution
+    let updated_at = updated_at.with_nanosecond(0).unwrap_or(updated_at);
+    let last_modified = updated_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok());
+    if since.is_some_and(|since| updated_at <= since) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::LAST_MODIFIED, last_modified)],
+        )
+            .into_response());
+    }
+
+    let values = load_cmi_values(&state, attempt_id).await.map_err(e500)?;
+    let mut body = outcome_json(&values, state.config.browsed_completes);
+    body["status"] = attempt.status.into();
+    body["updated_at"] = serde_json::json!(updated_at);
+    // lets the host correlate the grade without looking the attempt up
+    body["metadata"] = attempt.metadata.into();
+    Ok(([(header::LAST_MODIFIED, last_modified)], Json(body)).into_response())
+}
+
+/// `completion`, `success`, `score_raw` and `score_scaled` derived from an
+/// attempt's CMI values; shared by the grade endpoint and finish.
+fn outcome_json(
+    values: &serde_json::Map<String, serde_json::Value>,
+    browsed_completes: bool,
+) -> serde_json::Value {
+    let get = |el: &str| values.get(el).and_then(|v| v.as_str());
+    let num = |el: &str| get(el).and_then(|v| v.trim().parse::<f64>().ok());
+    let unified = runtime::unify_status(get("cmi.core.lesson_status"), browsed_completes);
+    let score_raw = num("cmi.core.score.raw");
+    serde_json::json!({
+        "completion": unified.completion,
+        "success": unified.success,
+        "score_raw": score_raw,
+        "score_scaled": get(runtime::SCORE_SCALED)
+            .and_then(runtime::parse_scaled_score)
+            .or_else(|| runtime::scaled_score(score_raw, num("cmi.core.score.min"), num("cmi.core.score.max"))),
+    })
+}
+
+/// Instructor override: mark the attempt complete (e.g. offline completion).
+async fn complete_attempt(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+
+    let rec = state
+        .attempts
+        .override_complete(attempt_id, state.config.audits("cmi.core.lesson_status"))
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    state.publish(
+        attempt_id,
+        "values",
+        serde_json::json!({ "cmi.core.lesson_status": "completed" }),
+    );
+    state.publish(
+        attempt_id,
+        "status",
+        serde_json::json!({ "status": AttemptStatus::Completed, "instructor_override": true }),
+    );
+
+    Ok(Json(rec))
+}
+
+/// Admin: move an attempt to another learner after a mistaken launch. The
+/// registration follows: renamed when the attempt is its only one, otherwise the
+/// attempt gets a fresh registration for the new learner. Completed attempts are frozen.
+async fn reassign_attempt(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<ReassignAttemptReq>,
+) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let learner_id = req.learner_id.trim();
+    if learner_id.is_empty() {
+        return Err(e400("learner_id is required"));
+    }
+
+    let current = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    if attempt_completed(&current) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("{} attempts cannot be reassigned", current.status),
+        ));
+    }
+
+    let rec = state
+        .attempts
+        .reassign_attempt(attempt_id, learner_id, req.learner_name.as_deref())
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+
+    Ok(Json(rec))
+}
+
+/// Admin: change an attempt's status, learner name or finish time. A completed
+/// attempt cannot be moved back to another status.
+async fn patch_attempt(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(req): Json<PatchAttemptReq>,
+) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let status = req
+        .status
+        .as_deref()
+        .map(|s| {
+            s.parse::<AttemptStatus>()
+                .map_err(|_| e400(format!("unknown status: {}", s)))
+        })
+        .transpose()?;
+
+    let current = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    // reopening is its own admin action (POST .../reopen), not a status edit
+    let finished = current
+        .status
+        .parse::<AttemptStatus>()
+        .is_ok_and(|s| s.is_finished());
+    if finished && status.is_some_and(|s| s.as_str() != current.status) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "attempt is {}; use POST /api/attempts/{}/reopen to reopen it",
+                current.status, attempt_id
+            ),
+        ));
+    }
+    if req.finished_at.is_some_and(|f| f < current.created_at) {
+        return Err(e400("finished_at is before the attempt was created"));
+    }
+
+    let patch = AttemptPatch {
+        status,
+        learner_name: req.learner_name.as_deref().map(|n| n.trim().to_string()),
+        finished_at: req.finished_at,
+    };
+    let rec = state
+        .attempts
+        .patch_attempt(attempt_id, &patch)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+
+    Ok(Json(rec))
+}
+
+/// Admin: put a finished attempt back in progress with finished_at cleared, so
+/// the learner can continue (and write again under LOCK_FINISHED_ATTEMPTS).
+async fn reopen_attempt(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Attempt>, (axum::http::StatusCode, String)> {
+    require_admin(&state.config, &headers)?;
+    let current = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    let finished = current
+        .status
+        .parse::<AttemptStatus>()
+        .is_ok_and(|s| s.is_finished());
+    if !finished {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "attempt is {}; only finished attempts can be reopened",
+                current.status
+            ),
+        ));
+    }
+
+    let rec = state
+        .attempts
+        .reopen_attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    state.publish(
+        attempt_id,
+        "status",
+        serde_json::json!({ "status": AttemptStatus::InProgress }),
+    );
+    Ok(Json(rec))
+}
+
+#[derive(serde::Deserialize, Default)]
+struct PlayerParams {
+    // "mobile" or "desktop"; otherwise guessed from the User-Agent
+    device: Option<String>,
+}
+
+async fn player_shell(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    Query(params): Query<PlayerParams>,
+    headers: HeaderMap,
+) -> Result<Html<String>, (axum::http::StatusCode, String)> {
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    let course = state
+        .courses
+        .course(attempt.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+
+    // Decide which href to launch, plus the manifest parameters and identifier of its SCO
+    let sco = launched_sco(&state, &attempt).await.map_err(e500)?;
+    if attempt.sco_id.is_some() && sco.is_none() {
+        return Err(e404("sco not found"));
+    }
+    let (href, parameters, identifier, mobile_href) = match sco {
+        Some(sco) => (
+            sco.launch_href,
+            sco.parameters,
+            Some(sco.identifier),
+            sco.mobile_launch_href,
+        ),
+        // the default href need not belong to a SCO
+        None => (
+            default_launch_href(&course, &state.config.data_dir),
+            None,
+            None,
+            None,
+        ),
+    };
+    let mobile = match params.device.as_deref() {
+        Some(d) => d.eq_ignore_ascii_case("mobile"),
+        None => headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(util::is_mobile_user_agent),
+    };
+    let href = match mobile_href {
+        Some(m) if mobile => m,
+        _ => href,
+    };
+
+    // ServeDir is mounted at /content; base_path is relative to DATA_DIR.
+    // Absolute hrefs (allowed unless STRICT_HREF) are external content, linked as-is.
+    let base_url = if util::is_absolute_url(&href) {
+        href.trim().to_string()
+    } else {
+        util::join_url_path(&["content", &course.base_path, &href])
+    };
+    let mut launch_url = util::with_launch_parameters(&base_url, parameters.as_deref());
+    // single-entry-point packages pick the SCO to render from a query parameter
+    if let (Some(name), Some(identifier)) = (&state.config.sco_param_name, &identifier) {
+        let param = format!(
+            "{}={}",
+            util::url_encode(name),
+            util::url_encode(identifier)
+        );
+        launch_url = util::with_launch_parameters(&launch_url, Some(&param));
+    }
+
+    let theme = &state.config.player;
+    let logo = theme
+        .logo_url
+        .as_deref()
+        .map(|u| format!("<img class='logo' src='{}' alt=''/>", util::html_escape(u)))
+        .unwrap_or_default();
+
+    // external launches need their origin in frame-src
+    let frame_src = reqwest::Url::parse(&launch_url)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .map(|u| format!(" {}", u.origin().ascii_serialization()))
+        .unwrap_or_default();
+    let children = serde_json::Value::Object(
+        runtime::CHILDREN
+            .iter()
+            .map(|(k, v)| (k.to_string(), (*v).into()))
+            .collect(),
+    );
+    let html = player::render(
+        &theme.template,
+        &[
+            ("attempt_id", attempt_id.to_string()),
+            ("launch_mode", course.launch_mode().as_str().to_string()),
+            // JSON string literal; `</` escaped so the URL cannot close the script element
+            (
+                "launch_url",
+                serde_json::Value::from(launch_url.as_str())
+                    .to_string()
+                    .replace("</", "<\\/"),
+            ),
+            (
+                "commit_on_unload",
+                state.config.commit_on_unload.to_string(),
+            ),
+            (
+                "commit_on_finish",
+                state.config.commit_on_finish.to_string(),
+            ),
+            (
+                "idle_seconds",
+                state.config.session_idle_seconds.to_string(),
+            ),
+            ("children", children.to_string()),
+            ("title", util::html_escape(&theme.title)),
+            ("accent", theme.accent_color.clone()),
+            ("logo", logo),
+            (
+                "img_src",
+                theme
+                    .logo_origin()
+                    .map(|o| format!(" {}", o))
+                    .unwrap_or_default(),
+            ),
+            ("frame_src", frame_src),
+        ],
+    );
+
+    Ok(Html(html))
+}
+
+/// Popup-side half of the popup launch mode's postMessage bridge.
+async fn player_bridge() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        player::BRIDGE_JS,
+    )
+}
+
+// --- Runtime endpoints (MVP) ---
+
+/// The SCO an attempt runs: its explicit `sco_id`, else the one the course's
+/// `default_launch_strategy` picks, else the one behind `default_launch_href`.
+/// `registration` is the attempt's registration, oldest first; `resume` only looks
+/// at attempts before this one, so the answer does not move as the registration grows.
+fn effective_sco<'a>(
+    attempt: &Attempt,
+    course: &Course,
+    scos: &'a [Sco],
+    registration: &[Attempt],
+    data_dir: &std::path::Path,
+) -> Option<&'a Sco> {
+    if let Some(sco_id) = attempt.sco_id {
+        return scos.iter().find(|s| s.id == sco_id);
+    }
+    let picked = match course.launch_strategy() {
+        LaunchStrategy::ManifestDefault => None,
+        LaunchStrategy::FirstSco => scos.iter().find(|s| s.is_visible),
+        LaunchStrategy::Resume => registration
+            .iter()
+            .take_while(|a| a.id != attempt.id)
+            .filter_map(|a| a.sco_id)
+            .last()
+            .and_then(|id| scos.iter().find(|s| s.id == id)),
+    };
+    picked.or_else(|| {
+        let href = default_launch_href(course, data_dir);
+        scos.iter().find(|s| s.launch_href == href)
+    })
+}
+
+/// The manifest default launch: the first candidate that actually exists on disk,
+/// since packages are not always complete.
+fn default_launch_href(course: &Course, data_dir: &std::path::Path) -> String {
+    manifest::first_existing_href(&data_dir.join(&course.base_path), &course.launch_candidates)
+        .unwrap_or_else(|| course.launch_href.clone())
+}
+
+/// `effective_sco` for one attempt, loading the course and registration.
+async fn launched_sco(state: &AppState, attempt: &Attempt) -> Result<Option<Sco>, sqlx::Error> {
+    if let Some(sco_id) = attempt.sco_id {
+        return state.courses.sco(sco_id).await;
+    }
+    let Some(course) = state.courses.course(attempt.course_id).await? else {
+        return Ok(None);
+    };
+    let scos = state.courses.scos_for_course(course.id).await?;
+    let registration = state
+        .attempts
+        .registration_attempts(attempt.registration_id)
+        .await?;
+    Ok(effective_sco(
+        attempt,
+        &course,
+        &scos,
+        &registration,
+        &state.config.data_dir,
+    )
+    .cloned())
+}
+
+/// Reported for `cmi.core.lesson_status` until the SCO stores one.
+const NOT_ATTEMPTED: &str = "not attempted";
+
+#[derive(serde::Deserialize, Default)]
+struct InitializeParams {
+    // meta=true returns each element as { value, updated_at } for debugging stale state
+    #[serde(default)]
+    meta: bool,
+}
+
+async fn rt_initialize(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    Query(params): Query<InitializeParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    // learner identity is read from the attempt on every launch, so a reassignment
+    // shows up on the next initialize
+    let student_name = attempt.learner_name.clone().unwrap_or_default();
+    // total_time is LMS-maintained: always seeded from the accumulator
+    let total_time = runtime::format_timespan_12(attempt.total_time_secs);
+    let (comments, launch_data) = launched_sco(&state, &attempt)
+        .await
+        .map_err(e500)?
+        .map_or((None, None), |sco| (sco.comments_from_lms, sco.launch_data));
+    // the preference is read-write: the attempt's language only fills an unset element
+    let language = match &attempt.language {
+        Some(lang) => {
+            let course = state
+                .courses
+                .course(attempt.course_id)
+                .await
+                .map_err(e500)?;
+            let element =
+                runtime::language_element(course.as_ref().map_or("1.2", |c| &c.scorm_version));
+            Some((element, lang.clone()))
+        }
+        None => None,
+    };
+
+    if !params.meta {
+        let mut map = load_cmi_values(&state, attempt_id).await.map_err(e500)?;
+        map.insert(
+            "cmi.core.student_id".into(),
+            attempt.learner_id.clone().into(),
+        );
+        map.insert("cmi.core.student_name".into(), student_name.into());
+        map.insert("cmi.core.total_time".into(), total_time.into());
+        if let Some(c) = comments {
+            map.insert("cmi.comments_from_lms".into(), c.into());
+        }
+        if let Some(d) = launch_data {
+            map.insert("cmi.launch_data".into(), d.into());
+        }
+        map.insert(
+            runtime::RANDOM_SEED.into(),
+            attempt.random_seed.to_string().into(),
+        );
+        map.entry("cmi.core.lesson_status")
+            .or_insert(NOT_ATTEMPTED.into());
+        if let Some((element, lang)) = language {
+            map.entry(element).or_insert(lang.into());
+        }
+        return Ok(Json(serde_json::json!({ "values": map })));
+    }
+
+    let rows = state.attempts.values(attempt_id).await.map_err(e500)?;
+    let mut map = serde_json::Map::new();
+    for r in rows {
+        let value = stored_value(&r);
+        map.insert(
+            r.element,
+            serde_json::json!({ "value": value, "updated_at": r.updated_at }),
+        );
+    }
+    map.insert(
+        "cmi.core.student_id".into(),
+        serde_json::json!({ "value": attempt.learner_id, "updated_at": null }),
+    );
+    map.insert(
+        "cmi.core.student_name".into(),
+        serde_json::json!({ "value": student_name, "updated_at": null }),
+    );
+    // last changed when a session was folded in on finish
+    map.insert(
+        "cmi.core.total_time".into(),
+        serde_json::json!({ "value": total_time, "updated_at": attempt.finished_at }),
+    );
+    map.entry("cmi.core.lesson_status")
+        .or_insert(serde_json::json!({ "value": NOT_ATTEMPTED, "updated_at": null }));
+    if let Some(c) = comments {
+        map.insert(
+            "cmi.comments_from_lms".into(),
+            serde_json::json!({ "value": c, "updated_at": null }),
+        );
+    }
+    if let Some(d) = launch_data {
+        map.insert(
+            "cmi.launch_data".into(),
+            serde_json::json!({ "value": d, "updated_at": null }),
+        );
+    }
+    map.insert(
+        runtime::RANDOM_SEED.into(),
+        serde_json::json!({ "value": attempt.random_seed.to_string(), "updated_at": attempt.created_at }),
+    );
+    if let Some((element, lang)) = language {
+        map.entry(element)
+            .or_insert(serde_json::json!({ "value": lang, "updated_at": attempt.created_at }));
+    }
+    Ok(Json(serde_json::json!({ "values": map })))
+}
+async fn rt_set(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    Json(_req): Json<RuntimeSetReq>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    state.check_write_rate(attempt_id)?;
+    state.check_writable(attempt_id).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+async fn rt_get(Json(req): Json<RuntimeGetReq>) -> impl IntoResponse {
+    // only the static `_children` keywords are answered here; values come from initialize
+    let value = runtime::children(&req.element).unwrap_or("");
+    Json(serde_json::json!({ "value": value }))
+}
+
+#[derive(serde::Deserialize, Default)]
+struct CommitParams {
+    // echo=true returns the persisted (normalized) values in the response
+    #[serde(default)]
+    echo: bool,
+}
+
+async fn rt_commit(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    Query(params): Query<CommitParams>,
+    body: axum::body::Bytes,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    // parsed regardless of content-type: navigator.sendBeacon posts text/plain
+    let map: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|e| e400(format!("invalid JSON body: {}", e)))?;
+    let obj = map.as_object().cloned().unwrap_or_default();
+    commit_values(&state, attempt_id, obj, params.echo)
+        .await
+        .map(Json)
+}
+
+/// Offline clients sync queued commits for several attempts in one call. Each entry
+/// runs through the same path as `/runtime/:id/commit`; a failing entry is reported
+/// in its own result and does not stop the others.
+async fn rt_commit_batch(
+    State(state): State<AppState>,
+    Json(req): Json<CommitBatchReq>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let mut results = Vec::with_capacity(req.commits.len());
+    for commit in req.commits {
+        let attempt_id = commit.attempt_id;
+        let exists = state
+            .attempts
+            .attempt(attempt_id)
+            .await
+            .map_err(e500)?
+            .is_some();
+        let mut res = if !exists {
+            serde_json::json!({ "ok": false, "error": "attempt not found" })
+        } else {
+            match commit_values(&state, attempt_id, commit.values, false).await {
+                Ok(res) => res,
+                Err((_, msg)) => serde_json::json!({ "ok": false, "error": msg }),
+            }
+        };
+        res["attempt_id"] = attempt_id.to_string().into();
+        results.push(res);
+    }
+    Ok(Json(serde_json::json!({ "results": results })))
+}
+
+/// Validate and store one commit's values, then apply the derived status/score
+/// updates and publish change events. Returns the commit response body.
+async fn commit_values(
+    state: &AppState,
+    attempt_id: Uuid,
+    obj: serde_json::Map<String, serde_json::Value>,
+    echo: bool,
+) -> Result<serde_json::Value, (axum::http::StatusCode, String)> {
+    state.check_write_rate(attempt_id)?;
+    state.check_writable(attempt_id).await?;
+    // lenient mode: `cmi.core.Lesson_Status` is validated and stored as `cmi.core.lesson_status`
+    let obj: serde_json::Map<String, serde_json::Value> = if state.config.case_insensitive_elements
+    {
+        obj.into_iter()
+            .map(|(el, v)| (runtime::canonical_element(&el).unwrap_or(el), v))
+            .collect()
+    } else {
+        obj
+    };
+
+    // per-attempt cap on distinct elements; updates to existing keys always pass
+    let max_elements = state.config.max_cmi_elements;
+    let mode = state.config.element_mode;
+    let stored = state.attempts.values(attempt_id).await.map_err(e500)?;
+    let mut known: std::collections::HashSet<String> =
+        stored.iter().map(|v| v.element.clone()).collect();
+
+    let attempt = state.attempts.attempt(attempt_id).await.map_err(e500)?;
+    let course = match &attempt {
+        Some(a) => state.courses.course(a.course_id).await.map_err(e500)?,
+        None => None,
+    };
+    // optional course-level allowlist on top of the built-in validators
+    let allowed: Option<Vec<String>> = course
+        .as_ref()
+        .and_then(|c| c.allowed_elements.clone())
+        .and_then(|v| serde_json::from_value(v).ok());
+    let has_minimum = course
+        .as_ref()
+        .is_some_and(|c| c.min_session_seconds.is_some_and(|m| m > 0));
+    let completion_held = match (&attempt, &course) {
+        (Some(a), Some(c)) => {
+            let session = match obj.get("cmi.core.session_time").and_then(|v| v.as_str()) {
+                Some(v) => Some(v),
+                None => stored
+                    .iter()
+                    .find(|v| v.element == "cmi.core.session_time")
+                    .and_then(|v| v.value.as_deref()),
+            };
+            completion_held(a, c, session)
+        }
+        _ => false,
+    };
+
+    let mut errors = serde_json::Map::new();
+    let mut changed = serde_json::Map::new();
+    for (el, val) in obj.iter() {
+        // Make an owned String so we never borrow a temporary.
+        let value: String = val
+            .as_str()
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| val.to_string());
+
+        // unsupported names are only stored in permissive mode, and only if sane
+        let storable_unknown = mode == runtime::ElementMode::Permissive
+            && !el.is_empty()
+            && el.len() <= runtime::MAX_ELEMENT_NAME_LEN;
+        let rejected = if runtime::is_read_only_element(el) {
+            Some(runtime::ScormError::ReadOnly)
+        } else if !runtime::is_writable_element(el) && !storable_unknown {
+            Some(runtime::ScormError::NotImplemented)
+        } else if allowed
+            .as_deref()
+            .is_some_and(|a| !runtime::element_allowed(el, a))
+        {
+            Some(runtime::ScormError::ReadOnly)
+        } else if runtime::interaction_field(el).is_some()
+            && !runtime::array_indices_valid(el, state.config.max_array_entries)
+        {
+            Some(runtime::ScormError::InvalidArgument)
+        } else if value.len() > runtime::max_len(el)
+            || (el == runtime::NAV_REQUEST && runtime::parse_nav_request(&value).is_none())
+            || (el == runtime::SCORE_SCALED && runtime::parse_scaled_score(&value).is_none())
+            || runtime::interaction_field(el)
+                .is_some_and(|f| !runtime::valid_interaction_value(f, &value))
+        {
+            Some(runtime::ScormError::IncorrectDataType)
+        } else if !known.contains(el) && known.len() >= max_elements {
+            Some(runtime::ScormError::GeneralException)
+        } else {
+            None
+        };
+        if let Some(err) = rejected {
+            errors.insert(el.clone(), err.code().into());
+            continue;
+        }
+        known.insert(el.clone());
+
+        let value = if *el == "cmi.core.lesson_status" {
+            let normalized = runtime::normalize_lesson_status(&value);
+            // kept aside for finish; any other status drops what was held
+            match normalized {
+                Some(held @ ("completed" | "passed" | "failed")) if completion_held => {
+                    state
+                        .attempts
+                        .put_value(attempt_id, runtime::HELD_LESSON_STATUS, held, false)
+                        .await
+                        .map_err(e500)?;
+                    "incomplete"
+                }
+                _ if has_minimum => {
+                    state
+                        .attempts
+                        .take_value(attempt_id, runtime::HELD_LESSON_STATUS)
+                        .await
+                        .map_err(e500)?;
+                    normalized.unwrap_or("incomplete")
+                }
+                _ => normalized.unwrap_or("incomplete"),
+            }
+            .to_string()
+        } else {
+            value
+        };
+        let compress = *el == "cmi.suspend_data" && state.config.compress_suspend_data;
+        let v_final = if compress {
+            runtime::compress_value(&value)
+        } else {
+            value.clone()
+        };
+
+        // re-committing the stored value is a no-op: no updated_at bump, log row or event
+        let written = state
+            .attempts
+            .put_value(attempt_id, el, &v_final, compress)
+            .await
+            .map_err(e500)?;
+        if !written {
+            continue;
+        }
+        // history keeps the readable value, not the compressed form
+        if state.config.audits(el) {
+            state
+                .attempts
+                .log_value(attempt_id, el, &value)
+                .await
+                .map_err(e500)?;
+        }
+        changed.insert(el.clone(), value.into());
+    }
+    // an LMS-side passed/failed decision shows up as a change too
+    let mastery = course.as_ref().and_then(|c| c.passing_score());
+    let complete_on_score =
+        course.as_ref().is_some_and(|c| c.complete_on_score) && !completion_held;
+    let audit = state.config.audits("cmi.core.lesson_status");
+    let (status, rewritten) =
+        derive_lesson_status(state, attempt_id, mastery, complete_on_score, audit)
+            .await
+            .map_err(e500)?;
+    if let (Some(status), true) = (&status, rewritten) {
+        changed.insert("cmi.core.lesson_status".into(), status.clone().into());
+    }
+    if changed
+        .keys()
+        .any(|el| el.starts_with("cmi.core.score.") || el == runtime::SCORE_SCALED)
+    {
+        sync_attempt_score(state, attempt_id).await.map_err(e500)?;
+    }
+    if !changed.is_empty() {
+        state.publish(attempt_id, "values", changed.into());
+    }
+
+    if let Some(finished) = status.as_deref().and_then(runtime::finished_status) {
+        let prev = state
+            .attempts
+            .set_status(attempt_id, finished)
+            .await
+            .map_err(e500)?;
+        if prev.is_some_and(|p| p != finished.as_str()) {
+            state.publish(
+                attempt_id,
+                "status",
+                serde_json::json!({ "status": finished }),
+            );
+        }
+    }
+
+    let mut res = serde_json::json!({ "ok": errors.is_empty() });
+    if !errors.is_empty() {
+        // element -> SCORM error code, for elements that were not stored
+        res["errors"] = errors.into();
+    }
+    if echo {
+        res["values"] = load_cmi_values(state, attempt_id)
+            .await
+            .map_err(e500)?
+            .into();
+    }
+
+    Ok(res)
+}
+
+async fn rt_finish(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    state.check_writable(attempt_id).await?;
+    let exit = state
+        .attempts
+        .value(attempt_id, "cmi.core.exit")
+        .await
+        .map_err(e500)?;
+    let lesson_status = state
+        .attempts
+        .value(attempt_id, "cmi.core.lesson_status")
+        .await
+        .map_err(e500)?;
+
+    let lesson_status = release_held_completion(&state, attempt_id)
+        .await
+        .map_err(e500)?
+        .or(lesson_status);
+
+    let status = runtime::next_attempt_status(exit.as_deref(), lesson_status.as_deref());
+    finalize_session(&state, attempt_id, status)
+        .await
+        .map_err(e500)?;
+
+    let nav = resolve_nav_request(&state, attempt_id)
+        .await
+        .map_err(e500)?;
+    // final rollup, so the player/host can redirect or pass back a grade without another request
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    let values = load_cmi_values(&state, attempt_id).await.map_err(e500)?;
+    let mut body = outcome_json(&values, state.config.browsed_completes);
+    body["ok"] = true.into();
+    body["nav"] = serde_json::json!(nav);
+    body["status"] = attempt.status.into();
+    body["total_time"] = runtime::format_timespan_12(attempt.total_time_secs).into();
+    body["return_url"] = attempt.return_url.into();
+    Ok(Json(body))
+}
+
+/// `runtime::derive_lesson_status` over a stored lesson_status and raw score. With
+/// `complete_on_score`, a numeric raw score and no status (or `not attempted`)
+/// counts as `completed`. Returns the effective status and whether it differs
+/// from the stored one.
+fn derived_lesson_status(
+    stored: Option<&str>,
+    raw: Option<&str>,
+    mastery: Option<f64>,
+    complete_on_score: bool,
+) -> (Option<String>, bool) {
+    let raw = raw.and_then(|v| v.trim().parse::<f64>().ok());
+    let unset = stored.is_none_or(|s| s == NOT_ATTEMPTED);
+    let status = match stored {
+        _ if complete_on_score && unset && raw.is_some() => "completed",
+        Some(status) => status,
+        None => return (None, false),
+    };
+    let derived = runtime::derive_lesson_status(status, raw, mastery);
+    (Some(derived.to_string()), stored != Some(derived))
+}
+
+/// Store the attempt's derived lesson_status (see `derived_lesson_status`); a
+/// rewrite is logged when `audit` is set. Returns the effective status and
+/// whether it was rewritten.
+async fn derive_lesson_status(
+    state: &AppState,
+    attempt_id: Uuid,
+    mastery: Option<f64>,
+    complete_on_score: bool,
+    audit: bool,
+) -> Result<(Option<String>, bool), sqlx::Error> {
+    let stored = state
+        .attempts
+        .value(attempt_id, "cmi.core.lesson_status")
+        .await?;
+    let raw = state
+        .attempts
+        .value(attempt_id, "cmi.core.score.raw")
+        .await?;
+    let (status, rewritten) = derived_lesson_status(
+        stored.as_deref(),
+        raw.as_deref(),
+        mastery,
+        complete_on_score,
+    );
+    if let (Some(derived), true) = (&status, rewritten) {
+        state
+            .attempts
+            .put_value(attempt_id, "cmi.core.lesson_status", derived, false)
+            .await?;
+        if audit {
+            state
+                .attempts
+                .log_value(attempt_id, "cmi.core.lesson_status", derived)
+                .await?;
+        }
+    }
+    Ok((status, rewritten))
+}
+
+/// Copy cmi.core.score.* into the attempt's score columns (non-numeric values become NULL).
+/// A reported cmi.score.scaled takes precedence over the computed scaled score.
+async fn sync_attempt_score(state: &AppState, attempt_id: Uuid) -> Result<(), sqlx::Error> {
+    let values = state.attempts.values(attempt_id).await?;
+    let get = |el: &str| {
+        values
+            .iter()
+            .find(|v| v.element == el)
+            .and_then(|v| v.value.as_deref())
+    };
+    let num = |el: &str| {
+        get(el)
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite())
+    };
+    let (raw, min, max) = (
+        num("cmi.core.score.raw"),
+        num("cmi.core.score.min"),
+        num("cmi.core.score.max"),
+    );
+    let scaled = get(runtime::SCORE_SCALED)
+        .and_then(runtime::parse_scaled_score)
+        .or_else(|| runtime::scaled_score(raw, min, max));
+    state
+        .attempts
+        .set_scores(
+            attempt_id,
+            AttemptScores {
+                raw,
+                min,
+                max,
+                scaled,
+            },
+        )
+        .await
+}
+
+/// Client-driven navigation: switch to `sco_id`, which runs under its own attempt
+/// in the registration (created on the first visit), and return that attempt plus
+/// the SCO's launch URL for the iframe.
+async fn rt_set_sco(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+    Json(req): Json<SetScoReq>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    check_sco_in_course(&state, attempt.course_id, Some(req.sco_id)).await?;
+    let course = state
+        .courses
+        .course(attempt.course_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("course not found"))?;
+    let scos = state
+        .courses
+        .scos_for_course(course.id)
+        .await
+        .map_err(e500)?;
+    let sco = scos
+        .iter()
+        .find(|s| s.id == req.sco_id)
+        .ok_or_else(|| e404("sco not found"))?;
+    let target = sco_attempt(&state, &attempt, &course, &scos, sco.id)
+        .await
+        .map_err(e500)?;
+
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "attempt_id": target.id,
+        "sco_id": sco.id,
+        "identifier": sco.identifier,
+        "launch_url": sco_launch_url(&course, sco),
+    })))
+}
+
+/// Idle timeout from the player: record `cmi.core.exit = suspend` and end the
+/// session as `suspended`, so a later launch resumes where the learner left off.
+async fn rt_suspend(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    if state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .is_none()
+    {
+        return Err(e404("attempt not found"));
+    }
+    state.check_writable(attempt_id).await?;
+    state
+        .attempts
+        .put_value(attempt_id, "cmi.core.exit", "suspend", false)
+        .await
+        .map_err(e500)?;
+
+    finalize_session(&state, attempt_id, AttemptStatus::Suspended)
+        .await
+        .map_err(e500)?;
+    Ok(Json(
+        serde_json::json!({ "ok": true, "status": AttemptStatus::Suspended }),
+    ))
+}
+
+/// Whether `min_session_seconds` still holds completion at incomplete: the
+/// attempt's prior sessions plus `session_time` are below the minimum.
+fn completion_held(attempt: &Attempt, course: &Course, session_time: Option<&str>) -> bool {
+    let min = course.min_session_seconds.unwrap_or(0);
+    let elapsed = attempt.total_time_secs
+        + session_time
+            .and_then(runtime::parse_timespan_12)
+            .unwrap_or(0.0);
+    min > 0 && elapsed < min as f64
+}
+
+/// On finish, with this session's time counted: once the minimum is met, apply
+/// the lesson status a commit held back and re-derive from the score, as that
+/// commit would have. Returns the new lesson status when one was written.
+async fn release_held_completion(
+    state: &AppState,
+    attempt_id: Uuid,
+) -> Result<Option<String>, sqlx::Error> {
+    let Some(attempt) = state.attempts.attempt(attempt_id).await? else {
+        return Ok(None);
+    };
+    let Some(course) = state.courses.course(attempt.course_id).await? else {
+        return Ok(None);
+    };
+    let session = state
+        .attempts
+        .value(attempt_id, "cmi.core.session_time")
+        .await?;
+    if course.min_session_seconds.unwrap_or(0) <= 0
+        || completion_held(&attempt, &course, session.as_deref())
+    {
+        return Ok(None);
+    }
+
+    let audit = state.config.audits("cmi.core.lesson_status");
+    let held = state
+        .attempts
+        .take_value(attempt_id, runtime::HELD_LESSON_STATUS)
+        .await?;
+    let mut released = None;
+    if let Some(held) = held {
+        if state
+            .attempts
+            .put_value(attempt_id, "cmi.core.lesson_status", &held, false)
+            .await?
+        {
+            if audit {
+                state
+                    .attempts
+                    .log_value(attempt_id, "cmi.core.lesson_status", &held)
+                    .await?;
+            }
+            released = Some(held);
+        }
+    }
+    let (status, rewritten) = derive_lesson_status(
+        state,
+        attempt_id,
+        course.passing_score(),
+        course.complete_on_score,
+        audit,
+    )
+    .await?;
+    let released = if rewritten { status } else { released };
+    if let Some(status) = &released {
+        state.publish(
+            attempt_id,
+            "values",
+            serde_json::json!({ "cmi.core.lesson_status": status }),
+        );
+    }
+    Ok(released)
+}
+
+/// End the current session: fold its session_time into the attempt's total_time
+/// and record the attempt's new status.
+async fn finalize_session(
+    state: &AppState,
+    attempt_id: Uuid,
+    status: AttemptStatus,
+) -> Result<(), sqlx::Error> {
+    let session_time = state
+        .attempts
+        .take_value(attempt_id, "cmi.core.session_time")
+        .await?;
+    let session_secs = session_time
+        .as_deref()
+        .and_then(runtime::parse_timespan_12)
+        .unwrap_or(0.0);
+
+    state
+        .attempts
+        .finish_session(attempt_id, status, session_secs)
+        .await?;
+    state.publish(
+        attempt_id,
+        "status",
+        serde_json::json!({ "status": status }),
+    );
+    Ok(())
+}
+
+/// SSE stream of an attempt's progress. The first event is a `snapshot` of the
+/// current status and values, so reconnecting clients catch up before live events.
+async fn attempt_events(
+    State(state): State<AppState>,
+    Path(attempt_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (axum::http::StatusCode, String)> {
+    // subscribe before reading the snapshot so no change falls in between
+    let rx = state.events.subscribe();
+    let attempt = state
+        .attempts
+        .attempt(attempt_id)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e404("attempt not found"))?;
+    let values = load_cmi_values(&state, attempt_id).await.map_err(e500)?;
+    let snapshot = Event::default()
+        .event("snapshot")
+        .json_data(serde_json::json!({ "status": attempt.status, "values": values }))
+        .map_err(e500)?;
+
+    // lagged receivers skip missed events; the next snapshot is one reconnect away
+    let live = BroadcastStream::new(rx).filter_map(move |ev| match ev {
+        Ok(ev) if ev.attempt_id == attempt_id => Event::default()
+            .event(ev.kind)
+            .json_data(&ev.data)
+            .ok()
+            .map(Ok),
+        _ => None,
+    });
+    let stream = tokio_stream::once(Ok(snapshot)).chain(live);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// The registration's attempt on `sco_id`, created on the first visit (one
+/// attempt per SCO). An attempt without a SCO runs its `effective_sco`.
+/// Learner, language, return URL and metadata carry over from `from`.
+async fn sco_attempt(
+    state: &AppState,
+    from: &Attempt,
+    course: &Course,
+    scos: &[Sco],
+    sco_id: Uuid,
+) -> Result<Attempt, sqlx::Error> {
+    let siblings = state
+        .attempts
+        .registration_attempts(from.registration_id)
+        .await?;
+    if let Some(existing) = siblings.iter().find(|a| {
+        effective_sco(a, course, scos, &siblings, &state.config.data_dir).map(|s| s.id)
+            == Some(sco_id)
+    }) {
+        return Ok(existing.clone());
+    }
+    let req = CreateAttemptReq {
+        course_id: from.course_id,
+        learner_id: from.learner_id.clone(),
+        learner_name: from.learner_name.clone(),
+        sco_id: Some(sco_id),
+        start_location: None,
+        registration_id: Some(from.registration_id),
+        language: from.language.clone(),
+        return_url: from.return_url.clone(),
+        metadata: from.metadata.clone(),
+    };
+    state
+        .attempts
+        .create_attempt(&req, from.registration_id)
+        .await
+}
+
+/// Resolve a pending `adl.nav.request` into the SCO to launch next and that
+/// SCO's attempt in the registration. The request is consumed;
+/// `continue`/`previous` walk SCOs in manifest order.
+async fn resolve_nav_request(
+    state: &AppState,
+    attempt_id: Uuid,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let raw = state
+        .attempts
+        .take_value(attempt_id, runtime::NAV_REQUEST)
+        .await?;
+    let Some(request) = raw.as_deref().and_then(runtime::parse_nav_request) else {
+        return Ok(None);
+    };
+
+    let Some(attempt) = state.attempts.attempt(attempt_id).await? else {
+        return Ok(None);
+    };
+    let Some(course) = state.courses.course(attempt.course_id).await? else {
+        return Ok(None);
+    };
+    let scos = state.courses.scos_for_course(attempt.course_id).await?;
+
+    let registration = state
+        .attempts
+        .registration_attempts(attempt.registration_id)
+        .await?;
+    let current = effective_sco(
+        &attempt,
+        &course,
+        &scos,
+        &registration,
+        &state.config.data_dir,
+    )
+    .and_then(|sco| scos.iter().position(|s| s.id == sco.id))
+    .unwrap_or(0);
+
+    let target = match &request {
+        runtime::NavRequest::Continue => scos.get(current + 1),
+        runtime::NavRequest::Previous => current.checked_sub(1).and_then(|i| scos.get(i)),
+        runtime::NavRequest::Choice(ident) => scos.iter().find(|s| &s.identifier == ident),
+        _ => None,
+    };
+    let Some(target) = target else {
+        return Ok(None);
+    };
+
+    // the navigated SCO runs under its own attempt in the registration
+    let target_attempt = sco_attempt(state, &attempt, &course, &scos, target.id).await?;
+
+    Ok(Some(serde_json::json!({
+        "request": raw,
+        "attempt_id": target_attempt.id,
+        "sco_id": target.id,
+        "identifier": target.identifier,
+        "launch_url": sco_launch_url(&course, target),
+    })))
+}
+
+// --- helpers ---
+
+/// `/content` URL of a SCO, with its manifest parameters applied.
+fn sco_launch_url(course: &Course, sco: &Sco) -> String {
+    util::with_launch_parameters(
+        &util::join_url_path(&["content", &course.base_path, &sco.launch_href]),
+        sco.parameters.as_deref(),
+    )
+}
+
+/// Admin endpoints require `Authorization: Bearer $ADMIN_TOKEN`; disabled when unset.
+fn require_admin(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<(), (axum::http::StatusCode, String)> {
+    let Some(expected) = config.admin_token.as_deref() else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "admin API disabled (ADMIN_TOKEN not set)".into(),
+        ));
+    };
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if given != Some(expected) {
+        return Err((StatusCode::UNAUTHORIZED, "admin token required".into()));
+    }
+    Ok(())
+}
+
+/// JSON response with a content-hash ETag; a matching If-None-Match gets 304.
+fn json_with_etag<T: serde::Serialize>(
+    headers: &HeaderMap,
+    body: &T,
+) -> Result<Response, (axum::http::StatusCode, String)> {
+    use sha2::{Digest, Sha256};
+    let bytes = serde_json::to_vec(body).map_err(e500)?;
+    let digest = Sha256::digest(&bytes);
+    let etag = format!(
+        "\"{}\"",
+        digest[..16]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+
+    let matches = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .map(|t| t.trim().trim_start_matches("W/"))
+                .any(|t| t == "*" || t == etag)
+        });
+    if matches {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    Ok((
+        [
+            (header::ETAG, etag),
+            (header::CONTENT_TYPE, "application/json".into()),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+/// A stored value as the SCO wrote it (decompressed when flagged).
+fn stored_value(row: &CmiValue) -> String {
+    let value = row.value.clone().unwrap_or_default();
+    if row.compressed {
+        runtime::decompress_value(value)
+    } else {
+        value
+    }
+}
+
+async fn load_cmi_values(
+    state: &AppState,
+    attempt_id: Uuid,
+) -> Result<serde_json::Map<String, serde_json::Value>, sqlx::Error> {
+    let rows = state.attempts.values(attempt_id).await?;
+
+    let mut map = serde_json::Map::new();
+    for r in rows {
+        // element is NOT NULL in schema; value may be NULL
+        let v = stored_value(&r);
+        map.insert(r.element, serde_json::Value::String(v));
+    }
+    Ok(map)
+}
+
+/// Multipart read failures keep their own status (413 when the body limit is hit).
+fn multipart_err(
+    err: axum::extract::multipart::MultipartError,
+) -> (axum::http::StatusCode, String) {
+    (err.status(), err.body_text())
+}
+
+/// Request deadlines: (default, upload routes).
+#[derive(Clone, Copy)]
+pub struct Timeouts {
+    pub default: std::time::Duration,
+    pub upload: std::time::Duration,
+}
+
+/// Fail a request with 408 once it runs past its deadline. Uploads extract and
+/// parse whole packages, so they get the longer `upload` budget. Only the time
+/// to produce the response counts; streamed bodies (SSE, JSONL) are unaffected.
+pub async fn request_timeout(
+    State(timeouts): State<Timeouts>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    let upload = matches!(
+        request.uri().path(),
+        "/api/courses/upload" | "/api/courses/upload_from_url"
+    );
+    let limit = if upload {
+        timeouts.upload
+    } else {
+        timeouts.default
+    };
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(res) => res,
+        Err(_) => (
+            StatusCode::REQUEST_TIMEOUT,
+            format!("request did not finish within {}s", limit.as_secs()),
+        )
+            .into_response(),
+    }
+}
+
+/// Body-limit rejections are plain text from axum; give clients a JSON body that
+/// names the configured limit. Already-converted responses (from the tighter
+/// runtime limit) pass through the outer, global layer untouched.
+pub async fn payload_too_large_json(State(limit): State<usize>, res: Response) -> Response {
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if res.status() != StatusCode::PAYLOAD_TOO_LARGE || is_json {
+        return res;
+    }
+    let body = serde_json::json!({
+        "error": {
+            "code": "payload_too_large",
+            "message": format!("request body exceeds the {} byte limit", limit),
+            "limit_bytes": limit,
+        }
+    });
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(body)).into_response()
+}
+
+fn e400<T: Into<String>>(msg: T) -> (axum::http::StatusCode, String) {
+    (axum::http::StatusCode::BAD_REQUEST, msg.into())
+}
+
+fn e404<T: Into<String>>(msg: T) -> (axum::http::StatusCode, String) {
+    (axum::http::StatusCode::NOT_FOUND, msg.into())
+}
+
+fn e500<E: std::fmt::Display>(e: E) -> (axum::http::StatusCode, String) {
+    tracing::error!(error=%e, "internal error");
+    (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::memory::MemRepo;
+    use axum::body::Body;
+    use axum::http::{Method, Request};
+
+    const ADMIN: &str = "Bearer test-admin";
+
+    /// Fresh, not yet created directory under the system temp dir.
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("scorm-test-{}", Uuid::new_v4()))
+    }
+
+    /// Defaults plus `vars`; DATA_DIR defaults to a fresh temp dir.
+    fn test_config(vars: &[(&str, &str)]) -> Config {
+        let data_dir = temp_path().to_string_lossy().into_owned();
+        Config::from_vars(|name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+                .or_else(|| match name {
+                    "DATABASE_URL" => Some("postgres://unused".into()),
+                    "ADMIN_TOKEN" => Some("test-admin".into()),
+                    "DATA_DIR" => Some(data_dir.clone()),
+                    _ => None,
+                })
+        })
+        .unwrap()
+    }
+
+    fn test_app(vars: &[(&str, &str)]) -> (Router, Arc<MemRepo>) {
+        let repo = Arc::new(MemRepo::default());
+        let state = AppState::with_repo(repo.clone(), test_config(vars));
+        (router(state), repo)
+    }
+
+    /// A course with one SCO per href, in order.
+    async fn seed_course(repo: &MemRepo, hrefs: &[&str]) -> (Course, Vec<Sco>) {
+        let dir = temp_path();
+        std::fs::create_dir_all(&dir).unwrap();
+        let items: String = (0..hrefs.len())
+            .map(|i| {
+                format!(r#"<item identifier="i{i}" identifierref="r{i}"><title>{i}</title></item>"#)
+            })
+            .collect();
+        let resources: String = hrefs
+            .iter()
+            .enumerate()
+            .map(|(i, h)| {
+                format!(r#"<resource identifier="r{i}" type="webcontent" adlcp:scormtype="sco" href="{h}"/>"#)
+            })
+            .collect();
+        let xml = format!(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>{items}</organization></organizations>
+<resources>{resources}</resources></manifest>"#
+        );
+        let path = dir.join("imsmanifest.xml");
+        std::fs::write(&path, xml).unwrap();
+        let parsed = manifest::parse_manifest(&path, 1 << 20).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let id = Uuid::new_v4();
+        let course = repo
+            .insert_course(id, "Test course", &id.to_string(), parsed)
+            .await
+            .unwrap();
+        let scos = repo.scos_for_course(course.id).await.unwrap();
+        (course, scos)
+    }
+
+    async fn send(
+        app: &Router,
+        method: Method,
+        uri: &str,
+        body: Option<serde_json::Value>,
+        admin: bool,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut req = Request::builder().method(method).uri(uri);
+        if admin {
+            req = req.header(header::AUTHORIZATION, ADMIN);
+        }
+        let body = match body {
+            Some(v) => {
+                req = req.header(header::CONTENT_TYPE, "application/json");
+                Body::from(v.to_string())
+            }
+            None => Body::empty(),
+        };
+        let res = app.clone().oneshot(req.body(body).unwrap()).await.unwrap();
+        let status = res.status();
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned().into());
+        (status, json)
+    }
+
+    async fn launch(app: &Router, course_id: Uuid, learner_id: &str) -> Uuid {
+        let (status, body) = send(
+            app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({ "course_id": course_id, "learner_id": learner_id })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn commit_and_finish_run_without_postgres() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({
+                "cmi.core.lesson_status": "passed",
+                "cmi.core.score.raw": "85",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/finish"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+        assert_eq!(attempt.status, AttemptStatus::Completed.as_str());
+        assert_eq!(attempt.score_raw, Some(85.0));
+        assert_eq!(
+            repo.value(attempt_id, "cmi.core.lesson_status")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("passed")
+        );
+    }
+
+    #[tokio::test]
+    async fn commit_echo_returns_normalized_lesson_status() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit?echo=true"),
+            Some(serde_json::json!({ "cmi.core.lesson_status": "done" })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["values"]["cmi.core.lesson_status"], "incomplete");
+
+        let (_, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({ "cmi.core.lesson_status": "done" })),
+            false,
+        )
+        .await;
+        assert!(body.get("values").is_none());
+    }
+
+    #[tokio::test]
+    async fn import_creates_course_and_sco_rows() {
+        let data_dir = temp_path();
+        std::fs::create_dir_all(data_dir.join("synced/a")).unwrap();
+        std::fs::write(data_dir.join("synced/a/one.html"), "").unwrap();
+        std::fs::write(data_dir.join("synced/a/two.html"), "").unwrap();
+        let (app, repo) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/courses/import",
+            Some(serde_json::json!({
+                "title": "Synced",
+                "base_path": "synced/a",
+                "launch_href": "one.html",
+                "scos": [
+                    { "identifier": "s1", "launch_href": "one.html" },
+                    { "identifier": "s2", "launch_href": "two.html" },
+                ],
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+        let course = repo.course(course_id).await.unwrap().unwrap();
+        assert_eq!(course.base_path, "synced/a");
+        assert_eq!(course.launch_href, "one.html");
+        let scos = repo.scos_for_course(course_id).await.unwrap();
+        let ids: Vec<_> = scos.iter().map(|s| s.identifier.as_str()).collect();
+        assert_eq!(ids, ["s1", "s2"]);
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/api/courses/import",
+            Some(serde_json::json!({
+                "title": "Broken",
+                "base_path": "synced/a",
+                "launch_href": "missing.html",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    async fn commit(app: &Router, attempt_id: Uuid, values: serde_json::Value) {
+        let (status, body) = send(
+            app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(values),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    async fn finish(app: &Router, attempt_id: Uuid) -> serde_json::Value {
+        let (status, body) = send(
+            app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/finish"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body
+    }
+
+    #[tokio::test]
+    async fn nav_continue_resolves_to_the_next_scos_own_attempt() {
+        let (app, repo) = test_app(&[]);
+        let (course, scos) = seed_course(&repo, &["one.html", "two.html"]).await;
+        let first = launch(&app, course.id, "learner-1").await;
+
+        commit(
+            &app,
+            first,
+            serde_json::json!({ "adl.nav.request": "continue" }),
+        )
+        .await;
+        let body = finish(&app, first).await;
+        assert_eq!(body["nav"]["identifier"], "i1");
+        assert_eq!(body["nav"]["sco_id"], scos[1].id.to_string());
+        assert!(body["nav"]["launch_url"]
+            .as_str()
+            .unwrap()
+            .ends_with("/two.html"));
+        let second: Uuid = body["nav"]["attempt_id"].as_str().unwrap().parse().unwrap();
+        assert_ne!(second, first);
+
+        // the finished attempt keeps its SCO; the target got its own attempt
+        let first_row = repo.attempt(first).await.unwrap().unwrap();
+        assert_eq!(first_row.sco_id, None);
+        let second_row = repo.attempt(second).await.unwrap().unwrap();
+        assert_eq!(second_row.sco_id, Some(scos[1].id));
+        assert_eq!(second_row.registration_id, first_row.registration_id);
+
+        // going back lands on the first SCO's existing attempt
+        commit(
+            &app,
+            second,
+            serde_json::json!({ "adl.nav.request": "previous" }),
+        )
+        .await;
+        let body = finish(&app, second).await;
+        assert_eq!(body["nav"]["attempt_id"], first.to_string());
+    }
+
+    #[tokio::test]
+    async fn compressed_suspend_data_round_trips() {
+        let (app, repo) = test_app(&[("COMPRESS_SUSPEND_DATA", "true")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let large: String = (0..600).map(|i| format!("q{i}=a;")).collect::<String>()[..4000].into();
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.suspend_data": large }),
+        )
+        .await;
+        let stored = repo.values(attempt_id).await.unwrap();
+        let row = stored
+            .iter()
+            .find(|v| v.element == "cmi.suspend_data")
+            .unwrap();
+        assert!(row.compressed);
+        assert!(row.value.as_ref().unwrap().len() < large.len());
+
+        let (_, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/initialize"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(body["values"]["cmi.suspend_data"], large.as_str());
+    }
+
+    #[tokio::test]
+    async fn plaintext_with_the_old_prefix_is_not_decoded() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        // valid base64 gzip, but written as plaintext
+        let lookalike = format!("gz64:{}", runtime::compress_value("x"));
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.suspend_data": lookalike }),
+        )
+        .await;
+        let (_, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/initialize"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(body["values"]["cmi.suspend_data"], lookalike.as_str());
+    }
+
+    #[tokio::test]
+    async fn bulk_creates_an_attempt_per_learner() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let learners: Vec<_> = (1..=5)
+            .map(|i| serde_json::json!({ "learner_id": format!("l{i}"), "learner_name": format!("Learner {i}") }))
+            .collect();
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts/bulk",
+            Some(serde_json::json!({ "course_id": course.id, "learners": learners })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let created = body["attempts"].as_object().unwrap();
+        assert_eq!(created.len(), 5);
+        for i in 1..=5 {
+            let id: Uuid = created[&format!("l{i}")].as_str().unwrap().parse().unwrap();
+            let attempt = repo.attempt(id).await.unwrap().unwrap();
+            assert_eq!(attempt.learner_id, format!("l{i}"));
+            assert_eq!(attempt.status, AttemptStatus::InProgress.as_str());
+        }
+    }
+
+    #[tokio::test]
+    async fn grade_is_not_modified_since_its_last_change() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.score.raw": "70" }),
+        )
+        .await;
+
+        let grade = |since: Option<String>| {
+            let mut req = Request::get(format!("/api/attempts/{attempt_id}/grade"));
+            if let Some(since) = since {
+                req = req.header(header::IF_MODIFIED_SINCE, since);
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+        let res = grade(None).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let last_modified = res.headers()[header::LAST_MODIFIED]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let res = grade(Some(last_modified.clone())).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        // a later write moves Last-Modified past the client's copy
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.score.raw": "90" }),
+        )
+        .await;
+        let res = grade(Some(last_modified)).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    /// In-memory zip of `(path, contents)` entries; paths ending in `/` are directories.
+    fn zip_of(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let opts = zip::write::FileOptions::default();
+        for (path, contents) in entries {
+            if path.ends_with('/') {
+                zip.add_directory(*path, opts).unwrap();
+            } else {
+                zip.start_file(*path, opts).unwrap();
+                zip.write_all(contents).unwrap();
+            }
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Multipart upload of `zip` with optional form `fields`.
+    async fn upload(
+        app: &Router,
+        zip: Vec<u8>,
+        fields: &[(&str, &str)],
+    ) -> (StatusCode, serde_json::Value) {
+        let boundary = "test-boundary";
+        let mut body = Vec::new();
+        for (name, value) in fields {
+            body.extend_from_slice(
+                format!("--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n")
+                    .as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"course.zip\"\r\n\
+                 Content-Type: application/zip\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&zip);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        let req = Request::post("/api/courses/upload")
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned().into());
+        (status, json)
+    }
+
+    #[tokio::test]
+    async fn scorm_2004_upload_is_rejected_unless_enabled() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_v1p3">
+<metadata><schema>ADL SCORM</schema><schemaversion>2004 4th Edition</schemaversion></metadata>
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="i0" identifierref="r0"><title>Lesson</title></item></organization></organizations>
+<resources><resource identifier="r0" type="webcontent" adlcp:scormType="sco" href="index.html"/></resources>
+</manifest>"#;
+        let zip = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("index.html", b"<html></html>"),
+        ]);
+
+        let data_dir = temp_path();
+        let (app, repo) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+        let (status, body) = upload(&app, zip.clone(), &[]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.as_str().unwrap().contains("SCORM 2004"), "{body}");
+        assert!(repo.list_courses().await.unwrap().is_empty());
+        // the extracted tree was removed again
+        let leftover = std::fs::read_dir(data_dir.join("courses"))
+            .map(|d| d.count())
+            .unwrap_or(0);
+        assert_eq!(leftover, 0);
+
+        let (app, _) = test_app(&[("ENABLE_SCORM_2004", "true")]);
+        let (status, body) = upload(&app, zip, &[]).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["scorm_version"], "2004");
+    }
+
+    #[tokio::test]
+    async fn element_cap_rejects_new_keys_but_not_updates() {
+        let (app, repo) = test_app(&[("MAX_CMI_ELEMENTS", "2")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p1", "cmi.core.exit": "" }),
+        )
+        .await;
+
+        let (_, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({
+                "cmi.core.lesson_location": "p2",
+                "cmi.suspend_data": "state",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(body["ok"], false);
+        assert_eq!(body["errors"]["cmi.suspend_data"], "101");
+        assert!(body["errors"].get("cmi.core.lesson_location").is_none());
+        assert_eq!(
+            repo.value(attempt_id, "cmi.core.lesson_location")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("p2")
+        );
+        assert_eq!(
+            repo.value(attempt_id, "cmi.suspend_data").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn finish_after_time_out_exit_is_timed_out() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.exit": "time-out" }),
+        )
+        .await;
+        let body = finish(&app, attempt_id).await;
+        assert_eq!(body["status"], "timed_out");
+        let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+        assert_eq!(attempt.status, AttemptStatus::TimedOut.as_str());
+        assert!(attempt.finished_at.is_some());
+    }
+
+    async fn player_page(app: &Router, attempt_id: Uuid) -> String {
+        let (status, body) = send(
+            app,
+            Method::GET,
+            &format!("/player/{attempt_id}"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body.as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn player_shows_configured_logo_and_accent() {
+        let (app, repo) = test_app(&[
+            ("PLAYER_LOGO_URL", "https://cdn.example.com/logo.png"),
+            ("PLAYER_ACCENT_COLOR", "#0a7cff"),
+        ]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let html = player_page(&app, attempt_id).await;
+        assert!(html.contains("<img class='logo' src='https://cdn.example.com/logo.png'"));
+        assert!(html.contains("--bar-bg:#0a7cff"));
+        // the logo's origin is allowed by the CSP
+        assert!(html.contains("img-src 'self' data: blob: https://cdn.example.com"));
+    }
+
+    async fn initialize(app: &Router, attempt_id: Uuid) -> serde_json::Value {
+        let (status, body) = send(
+            app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/initialize"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body["values"].clone()
+    }
+
+    #[tokio::test]
+    async fn start_location_seeds_only_new_attempts() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "start_location": "page-7",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let attempt_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+
+        let values = initialize(&app, attempt_id).await;
+        assert_eq!(values["cmi.core.lesson_location"], "page-7");
+        assert_eq!(values["cmi.core.entry"], "resume");
+
+        // relaunching into the registration reuses the attempt and keeps the learner's bookmark
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "page-9" }),
+        )
+        .await;
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "registration_id": body["registration_id"],
+                "start_location": "page-1",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["id"], attempt_id.to_string());
+        assert_eq!(
+            repo.value(attempt_id, "cmi.core.lesson_location")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("page-9")
+        );
+    }
+
+    #[tokio::test]
+    async fn beacon_commit_with_text_plain_body_is_persisted() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        // navigator.sendBeacon with a string body sends text/plain
+        let req = Request::post(format!("/runtime/{attempt_id}/commit"))
+            .header(header::CONTENT_TYPE, "text/plain;charset=UTF-8")
+            .body(Body::from(r#"{"cmi.core.lesson_location":"slide-3"}"#))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            repo.value(attempt_id, "cmi.core.lesson_location")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("slide-3")
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_and_directory_only_zips_are_rejected() {
+        let (app, repo) = test_app(&[]);
+        for zip in [
+            Vec::new(),
+            zip_of(&[]),
+            zip_of(&[("lesson/", b""), ("lesson/media/", b"")]),
+        ] {
+            let (status, body) = upload(&app, zip, &[]).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body, "package contains no files");
+        }
+        assert!(repo.list_courses().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn instructor_override_completes_and_publishes_status() {
+        let repo = Arc::new(MemRepo::default());
+        let state = AppState::with_repo(repo.clone(), test_config(&[]));
+        let mut events = state.events.subscribe();
+        let app = router(state);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let uri = format!("/api/attempts/{attempt_id}/complete");
+        let (status, _) = send(&app, Method::POST, &uri, None, false).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(&app, Method::POST, &uri, None, true).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["status"], "completed");
+        assert_eq!(body["instructor_override"], true);
+        assert!(body["finished_at"].is_string());
+        assert_eq!(
+            repo.value(attempt_id, "cmi.core.lesson_status")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("completed")
+        );
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            assert_eq!(event.attempt_id, attempt_id);
+            if event.kind == "status" {
+                assert_eq!(event.data["status"], "completed");
+                assert_eq!(event.data["instructor_override"], true);
+            }
+            kinds.push(event.kind);
+        }
+        assert_eq!(kinds, ["values", "status"]);
+    }
+
+    #[tokio::test]
+    async fn resumed_attempt_is_seeded_with_prior_total_time() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.session_time": "00:01:30", "cmi.core.exit": "suspend" }),
+        )
+        .await;
+        finish(&app, attempt_id).await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.session_time": "00:00:45" }),
+        )
+        .await;
+        finish(&app, attempt_id).await;
+
+        let values = initialize(&app, attempt_id).await;
+        assert_eq!(values["cmi.core.total_time"], "0000:02:15");
+    }
+
+    #[tokio::test]
+    async fn course_allowlist_rejects_other_elements() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::PUT,
+            &format!("/api/courses/{}/allowed_elements", course.id),
+            Some(serde_json::json!({ "allowed_elements": ["cmi.core.lesson_status"] })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (_, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({
+                "cmi.core.lesson_status": "incomplete",
+                "cmi.suspend_data": "state",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(body["ok"], false);
+        assert_eq!(body["errors"]["cmi.suspend_data"], "403");
+        assert!(repo
+            .value(attempt_id, "cmi.suspend_data")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repo
+            .value(attempt_id, "cmi.core.lesson_status")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn declared_thumbnail_is_served() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<metadata><thumbnail href="art/cover.png"/></metadata>
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="i0" identifierref="r0"><title>Lesson</title></item></organization></organizations>
+<resources><resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="index.html"/></resources>
+</manifest>"#;
+        let png = b"\x89PNG\r\n\x1a\nfake";
+        let (app, _) = test_app(&[]);
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("index.html", b""),
+                ("art/cover.png", png),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["thumbnail_href"], "art/cover.png");
+
+        let req = Request::get(format!(
+            "/api/courses/{}/thumbnail",
+            body["id"].as_str().unwrap()
+        ))
+        .body(Body::empty())
+        .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "image/png");
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], png);
+    }
+
+    #[tokio::test]
+    async fn attempt_with_another_courses_sco_is_rejected() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (_, other_scos) = seed_course(&repo, &["other.html"]).await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "sco_id": other_scos[0].id,
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "sco_id does not belong to course_id");
+    }
+
+    async fn next_frame(frames: &mut axum::body::BodyDataStream) -> String {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), frames.next())
+            .await
+            .expect("event within 5s")
+            .unwrap()
+            .unwrap();
+        String::from_utf8(frame.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn event_stream_delivers_committed_values() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let req = Request::get(format!("/api/attempts/{attempt_id}/events"))
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let mut frames = res.into_body().into_data_stream();
+        assert!(next_frame(&mut frames)
+            .await
+            .starts_with("event: snapshot\n"));
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p2" }),
+        )
+        .await;
+        let frame = next_frame(&mut frames).await;
+        assert!(frame.starts_with("event: values\n"), "{frame}");
+        assert!(
+            frame.contains(r#""cmi.core.lesson_location":"p2""#),
+            "{frame}"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_element_is_rejected_or_stored_by_mode() {
+        for (mode, stored) in [("strict", false), ("permissive", true)] {
+            let (app, repo) = test_app(&[("ELEMENT_MODE", mode)]);
+            let (course, _) = seed_course(&repo, &["index.html"]).await;
+            let attempt_id = launch(&app, course.id, "learner-1").await;
+
+            let (_, body) = send(
+                &app,
+                Method::POST,
+                &format!("/runtime/{attempt_id}/commit"),
+                Some(serde_json::json!({ "cmi.vendor.custom": "x" })),
+                false,
+            )
+            .await;
+            assert_eq!(body["ok"], stored, "{mode}: {body}");
+            if !stored {
+                assert_eq!(body["errors"]["cmi.vendor.custom"], "401");
+            }
+            let value = repo.value(attempt_id, "cmi.vendor.custom").await.unwrap();
+            assert_eq!(value.is_some(), stored, "{mode}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unchanged_course_is_not_modified() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let get = |etag: Option<&str>| {
+            let mut req = Request::get(format!("/api/courses/{}", course.id));
+            if let Some(etag) = etag {
+                req = req.header(header::IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+
+        let res = get(None).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        let res = get(Some(&etag)).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        repo.update_course(course.id, CourseSetting::MaxAttempts(Some(3)))
+            .await
+            .unwrap();
+        let res = get(Some(&etag)).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    /// Entries left under DATA_DIR/courses and the default PACKAGE_DIR beside it.
+    fn stored_uploads(data_dir: &std::path::Path) -> usize {
+        let mut package_dir = data_dir.as_os_str().to_owned();
+        package_dir.push("-packages");
+        [data_dir.join("courses"), package_dir.into()]
+            .iter()
+            .map(|d| std::fs::read_dir(d).map_or(0, |e| e.count()))
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn manifest_less_zip_launches_its_index_page() {
+        let data_dir = temp_path();
+        let (app, _) = test_app(&[
+            ("ALLOW_NO_MANIFEST", "true"),
+            ("DATA_DIR", data_dir.to_str().unwrap()),
+        ]);
+        let (status, body) = upload(
+            &app,
+            zip_of(&[("index.html", b"<h1>hi</h1>"), ("app.js", b"")]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["launch_href"], "index.html");
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+
+        let attempt_id = launch(&app, course_id, "learner-1").await;
+        let html = player_page(&app, attempt_id).await;
+        let launch_url = format!("/content/courses/{course_id}/index.html");
+        assert!(html.contains(&launch_url));
+        let (status, body) = send(&app, Method::GET, &launch_url, None, false).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "<h1>hi</h1>");
+    }
+
+    #[tokio::test]
+    async fn original_packages_are_not_served_under_content() {
+        let (app, _) = test_app(&[("ALLOW_NO_MANIFEST", "true")]);
+        let (status, body) = upload(&app, zip_of(&[("index.html", b"<h1>hi</h1>")]), &[]).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id = body["id"].as_str().unwrap();
+
+        let uri = format!("/api/courses/{course_id}/package");
+        let (status, _) = send(&app, Method::GET, &uri, None, true).await;
+        assert_eq!(status, StatusCode::OK);
+        let uri = format!("/content/packages/{course_id}.zip");
+        let (status, _) = send(&app, Method::GET, &uri, None, false).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn rejected_uploads_leave_nothing_behind() {
+        let data_dir = temp_path();
+        let (app, repo) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+        let rejected = [
+            // no manifest, fallback not enabled
+            zip_of(&[("index.html", b"")]),
+            // unparseable manifest
+            zip_of(&[("imsmanifest.xml", b"<manifest><organizations>")]),
+        ];
+        for zip in rejected {
+            let (status, body) = upload(&app, zip, &[]).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+            assert_eq!(stored_uploads(&data_dir), 0);
+        }
+
+        let (app, _) = test_app(&[
+            ("ALLOW_NO_MANIFEST", "true"),
+            ("DATA_DIR", data_dir.to_str().unwrap()),
+        ]);
+        let (status, _) = upload(&app, zip_of(&[("readme.txt", b"")]), &[]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(stored_uploads(&data_dir), 0);
+        assert!(repo.list_courses().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn initialize_meta_includes_updated_at() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p1" }),
+        )
+        .await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/initialize?meta=true"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let entry = &body["values"]["cmi.core.lesson_location"];
+        assert_eq!(entry["value"], "p1");
+        let stored = repo.values(attempt_id).await.unwrap();
+        let updated_at: chrono::DateTime<chrono::Utc> =
+            serde_json::from_value(entry["updated_at"].clone()).unwrap();
+        assert_eq!(updated_at, stored[0].updated_at);
+    }
+
+    #[tokio::test]
+    async fn terminal_status_is_held_until_the_minimum_time() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        repo.update_course(course.id, CourseSetting::MinSessionSeconds(Some(300)))
+            .await
+            .unwrap();
+
+        for status in ["completed", "passed", "failed"] {
+            let attempt_id = launch(&app, course.id, &format!("learner-{status}")).await;
+            commit(
+                &app,
+                attempt_id,
+                serde_json::json!({ "cmi.core.session_time": "00:01:00", "cmi.core.lesson_status": status }),
+            )
+            .await;
+            let stored = repo
+                .value(attempt_id, "cmi.core.lesson_status")
+                .await
+                .unwrap();
+            assert_eq!(stored.as_deref(), Some("incomplete"), "{status}");
+            let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+            assert_eq!(attempt.status, AttemptStatus::InProgress.as_str());
+
+            commit(
+                &app,
+                attempt_id,
+                serde_json::json!({ "cmi.core.session_time": "00:05:00", "cmi.core.lesson_status": status }),
+            )
+            .await;
+            let stored = repo
+                .value(attempt_id, "cmi.core.lesson_status")
+                .await
+                .unwrap();
+            assert_eq!(stored.as_deref(), Some(status));
+        }
+    }
+
+    #[tokio::test]
+    async fn held_status_applies_on_finish_once_the_minimum_is_met() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        repo.update_course(course.id, CourseSetting::MinSessionSeconds(Some(300)))
+            .await
+            .unwrap();
+
+        for (status, finished) in [
+            ("completed", "completed"),
+            ("passed", "completed"),
+            ("failed", "failed"),
+        ] {
+            let attempt_id = launch(&app, course.id, &format!("learner-{status}")).await;
+            commit(
+                &app,
+                attempt_id,
+                serde_json::json!({ "cmi.core.session_time": "00:01:00", "cmi.core.lesson_status": status }),
+            )
+            .await;
+            // the SCO reports its final session time, but not the status again
+            commit(
+                &app,
+                attempt_id,
+                serde_json::json!({ "cmi.core.session_time": "00:06:00" }),
+            )
+            .await;
+            let body = finish(&app, attempt_id).await;
+            assert_eq!(body["status"], finished, "{status}");
+            let stored = repo
+                .value(attempt_id, "cmi.core.lesson_status")
+                .await
+                .unwrap();
+            assert_eq!(stored.as_deref(), Some(status));
+            let held = repo
+                .value(attempt_id, runtime::HELD_LESSON_STATUS)
+                .await
+                .unwrap();
+            assert_eq!(held, None);
+        }
+
+        // still short of the minimum at finish: the hold stays
+        let attempt_id = launch(&app, course.id, "learner-short").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.session_time": "00:02:00", "cmi.core.lesson_status": "completed" }),
+        )
+        .await;
+        finish(&app, attempt_id).await;
+        let stored = repo
+            .value(attempt_id, "cmi.core.lesson_status")
+            .await
+            .unwrap();
+        assert_eq!(stored.as_deref(), Some("incomplete"));
+    }
+
+    #[tokio::test]
+    async fn events_jsonl_has_a_line_per_logged_write() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p1" }),
+        )
+        .await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p2", "cmi.suspend_data": "s" }),
+        )
+        .await;
+        // unchanged values are not written, so not logged
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p2" }),
+        )
+        .await;
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{}/events.jsonl", course.id),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let lines: Vec<serde_json::Value> = body
+            .as_str()
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        let writes: Vec<_> = lines
+            .iter()
+            .map(|l| (l["element"].as_str().unwrap(), l["value"].as_str().unwrap()))
+            .collect();
+        assert!(writes.contains(&("cmi.core.lesson_location", "p1")));
+        assert!(writes.contains(&("cmi.core.lesson_location", "p2")));
+        assert!(writes.contains(&("cmi.suspend_data", "s")));
+        assert!(lines.iter().all(|l| l["learner_id"] == "learner-1"));
+    }
+
+    async fn launch_sco(
+        app: &Router,
+        course_id: Uuid,
+        sco_id: Uuid,
+        registration_id: Option<Uuid>,
+    ) -> serde_json::Value {
+        let (status, body) = send(
+            app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course_id,
+                "learner_id": "learner-1",
+                "sco_id": sco_id,
+                "registration_id": registration_id,
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body
+    }
+
+    #[tokio::test]
+    async fn registration_scos_keep_own_state_and_share_rollup() {
+        let (app, repo) = test_app(&[]);
+        let (course, scos) = seed_course(&repo, &["one.html", "two.html"]).await;
+        let first = launch_sco(&app, course.id, scos[0].id, None).await;
+        let registration_id: Uuid = first["registration_id"].as_str().unwrap().parse().unwrap();
+        let second = launch_sco(&app, course.id, scos[1].id, Some(registration_id)).await;
+        let first: Uuid = first["id"].as_str().unwrap().parse().unwrap();
+        let second: Uuid = second["id"].as_str().unwrap().parse().unwrap();
+        assert_ne!(first, second);
+        // relaunching a SCO reuses its attempt
+        let again = launch_sco(&app, course.id, scos[0].id, Some(registration_id)).await;
+        assert_eq!(again["id"], first.to_string());
+
+        commit(
+            &app,
+            first,
+            serde_json::json!({ "cmi.core.lesson_location": "a" }),
+        )
+        .await;
+        commit(
+            &app,
+            second,
+            serde_json::json!({ "cmi.core.lesson_location": "b" }),
+        )
+        .await;
+        assert_eq!(
+            initialize(&app, first).await["cmi.core.lesson_location"],
+            "a"
+        );
+        assert_eq!(
+            initialize(&app, second).await["cmi.core.lesson_location"],
+            "b"
+        );
+
+        let registration = |app: Router| async move {
+            send(
+                &app,
+                Method::GET,
+                &format!("/api/registrations/{registration_id}"),
+                None,
+                false,
+            )
+            .await
+            .1
+        };
+        commit(
+            &app,
+            first,
+            serde_json::json!({ "cmi.core.lesson_status": "completed" }),
+        )
+        .await;
+        let body = registration(app.clone()).await;
+        assert_eq!(body["status"], "in_progress");
+        assert_eq!(body["attempts"].as_array().unwrap().len(), 2);
+
+        commit(
+            &app,
+            second,
+            serde_json::json!({ "cmi.core.lesson_status": "passed" }),
+        )
+        .await;
+        assert_eq!(registration(app.clone()).await["status"], "completed");
+    }
+
+    #[tokio::test]
+    async fn new_attempt_starts_not_attempted() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let values = initialize(&app, attempt_id).await;
+        assert_eq!(values["cmi.core.lesson_status"], "not attempted");
+    }
+
+    /// SCORM 1.2 manifest with a single SCO launching `href`.
+    fn single_sco_manifest(href: &str) -> String {
+        format!(
+            r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="i0" identifierref="r0"><title>Lesson</title></item></organization></organizations>
+<resources><resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="{href}"/></resources>
+</manifest>"#
+        )
+    }
+
+    #[tokio::test]
+    async fn verify_launch_on_upload_rejects_missing_or_empty_launch_file() {
+        let data_dir = temp_path();
+        let vars = [
+            ("VERIFY_LAUNCH_ON_UPLOAD", "true"),
+            ("DATA_DIR", data_dir.to_str().unwrap()),
+        ];
+        let (app, repo) = test_app(&vars);
+        let manifest = single_sco_manifest("lesson/start.html?page=1");
+
+        let missing = zip_of(&[("imsmanifest.xml", manifest.as_bytes())]);
+        let (status, body) = upload(&app, missing, &[]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "launch file not found in package: lesson/start.html");
+
+        let empty = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("lesson/start.html", b""),
+        ]);
+        let (status, body) = upload(&app, empty, &[]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "launch file is empty: lesson/start.html");
+        assert_eq!(stored_uploads(&data_dir), 0);
+        assert!(repo.list_courses().await.unwrap().is_empty());
+
+        let ok = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("lesson/start.html", b"<html></html>"),
+        ]);
+        let (status, body) = upload(&app, ok, &[]).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    #[tokio::test]
+    async fn suspend_records_exit_and_suspended_status() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        initialize(&app, attempt_id).await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/suspend"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["status"], "suspended");
+
+        let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+        assert_eq!(attempt.status, AttemptStatus::Suspended.as_str());
+        assert!(attempt.finished_at.is_some());
+        assert_eq!(
+            repo.value(attempt_id, "cmi.core.exit")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("suspend")
+        );
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{}/suspend", Uuid::new_v4()),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn committed_score_is_denormalized_onto_the_attempt() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({
+                "cmi.core.score.raw": "42",
+                "cmi.core.score.min": "10",
+                "cmi.core.score.max": "60",
+            }),
+        )
+        .await;
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/attempts/{attempt_id}"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["score_raw"], 42.0);
+        assert_eq!(body["score_min"], 10.0);
+        assert_eq!(body["score_max"], 60.0);
+        assert_eq!(body["score_scaled"], 0.64);
+    }
+
+    #[tokio::test]
+    async fn sco_comments_from_lms_reach_only_that_sco() {
+        let (app, repo) = test_app(&[]);
+        let (course, scos) = seed_course(&repo, &["one.html", "two.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::PUT,
+            &format!("/api/scos/{}/comments_from_lms", scos[0].id),
+            Some(serde_json::json!({ "comments_from_lms": "Review section 2" })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let first = launch_sco(&app, course.id, scos[0].id, None).await;
+        let first_id: Uuid = first["id"].as_str().unwrap().parse().unwrap();
+        let registration = first["registration_id"].as_str().unwrap().parse().ok();
+        let second = launch_sco(&app, course.id, scos[1].id, registration).await;
+        let second_id: Uuid = second["id"].as_str().unwrap().parse().unwrap();
+
+        let values = initialize(&app, first_id).await;
+        assert_eq!(values["cmi.comments_from_lms"], "Review section 2");
+        let values = initialize(&app, second_id).await;
+        assert!(values.get("cmi.comments_from_lms").is_none());
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{first_id}/commit"),
+            Some(serde_json::json!({ "cmi.comments_from_lms": "overwritten" })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["errors"]["cmi.comments_from_lms"], "403");
+        let values = initialize(&app, first_id).await;
+        assert_eq!(values["cmi.comments_from_lms"], "Review section 2");
+    }
+
+    #[tokio::test]
+    async fn recompute_applies_a_new_mastery_score() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let scored = |raw: &str| serde_json::json!({ "cmi.core.lesson_status": "completed", "cmi.core.score.raw": raw });
+
+        let finished = launch(&app, course.id, "learner-1").await;
+        commit(&app, finished, scored("70")).await;
+        finish(&app, finished).await;
+        let suspended = launch(&app, course.id, "learner-2").await;
+        commit(&app, suspended, scored("40")).await;
+        send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{suspended}/suspend"),
+            None,
+            false,
+        )
+        .await;
+        let open = launch(&app, course.id, "learner-3").await;
+        commit(&app, open, scored("80")).await;
+        let finished_at = repo.attempt(finished).await.unwrap().unwrap().finished_at;
+        let suspended_at = repo.attempt(suspended).await.unwrap().unwrap().finished_at;
+
+        let (status, body) = send(
+            &app,
+            Method::PUT,
+            &format!("/api/courses/{}/mastery_score", course.id),
+            Some(serde_json::json!({ "mastery_score": 60 })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/api/courses/{}/recompute", course.id),
+            None,
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["changed"], 3);
+
+        for (id, expected) in [
+            (finished, "passed"),
+            (suspended, "failed"),
+            (open, "passed"),
+        ] {
+            let stored = repo.value(id, "cmi.core.lesson_status").await.unwrap();
+            assert_eq!(stored.as_deref(), Some(expected));
+        }
+
+        let attempt = repo.attempt(finished).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "completed");
+        assert_eq!(attempt.finished_at, finished_at);
+        let attempt = repo.attempt(suspended).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "suspended");
+        assert_eq!(attempt.finished_at, suspended_at);
+        let attempt = repo.attempt(open).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "completed");
+        assert!(attempt.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn random_seed_is_stable_across_sessions() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let other_id = launch(&app, course.id, "learner-2").await;
+
+        let seed = initialize(&app, attempt_id).await[runtime::RANDOM_SEED].clone();
+        assert!(seed.is_string(), "{seed}");
+        finish(&app, attempt_id).await;
+        assert_eq!(
+            initialize(&app, attempt_id).await[runtime::RANDOM_SEED],
+            seed
+        );
+        assert_ne!(initialize(&app, other_id).await[runtime::RANDOM_SEED], seed);
+    }
+
+    #[tokio::test]
+    async fn invisible_item_is_kept_as_a_sco_but_left_out_of_the_menu() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="shown" identifierref="r0"><title>Lesson</title></item>
+<item identifier="hidden" identifierref="r1" isvisible="false"><title>Remediation</title></item>
+</organization></organizations>
+<resources>
+<resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="lesson.html"/>
+<resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="extra.html"/>
+</resources></manifest>"#;
+        let (app, repo) = test_app(&[]);
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("lesson.html", b"<html></html>"),
+                ("extra.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+
+        let scos = repo.scos_for_course(course_id).await.unwrap();
+        let flags: Vec<_> = scos
+            .iter()
+            .map(|s| (s.identifier.as_str(), s.is_visible))
+            .collect();
+        assert_eq!(flags, [("shown", true), ("hidden", false)]);
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{course_id}/menu"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let items = body["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["identifier"], "shown");
+    }
+
+    #[tokio::test]
+    async fn oversized_manifest_upload_is_rejected_cleanly() {
+        let data_dir = temp_path();
+        let (app, repo) = test_app(&[
+            ("MAX_MANIFEST_BYTES", "256"),
+            ("DATA_DIR", data_dir.to_str().unwrap()),
+        ]);
+        let manifest = format!(
+            "{}<!--{}-->",
+            single_sco_manifest("index.html"),
+            "x".repeat(256)
+        );
+        let zip = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("index.html", b"<html></html>"),
+        ]);
+
+        let (status, body) = upload(&app, zip, &[]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "imsmanifest.xml exceeds 256 bytes");
+        assert_eq!(stored_uploads(&data_dir), 0);
+        assert!(repo.list_courses().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn served_text_content_is_labelled_utf8() {
+        for (vars, suffix) in [
+            (&[][..], "; charset=utf-8"),
+            (&[("CONTENT_UTF8_CHARSET", "false")][..], ""),
+        ] {
+            let (app, _) = test_app(vars);
+            let manifest = single_sco_manifest("index.html");
+            let (status, body) = upload(
+                &app,
+                zip_of(&[
+                    ("imsmanifest.xml", manifest.as_bytes()),
+                    ("index.html", "<p>Grüße</p>".as_bytes()),
+                    ("style.css", b"p {}"),
+                ]),
+                &[],
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            let base = format!("/content/courses/{}", body["id"].as_str().unwrap());
+
+            for (file, mime) in [("index.html", "text/html"), ("style.css", "text/css")] {
+                let req = Request::get(format!("{base}/{file}"))
+                    .body(Body::empty())
+                    .unwrap();
+                let res = app.clone().oneshot(req).await.unwrap();
+                assert_eq!(res.status(), StatusCode::OK);
+                assert_eq!(
+                    res.headers()[header::CONTENT_TYPE],
+                    format!("{mime}{suffix}")
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn commit_batch_persists_each_attempt_independently() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let first = launch(&app, course.id, "learner-1").await;
+        let second = launch(&app, course.id, "learner-2").await;
+        let missing = Uuid::new_v4();
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/runtime/commit_batch",
+            Some(serde_json::json!({ "commits": [
+                { "attempt_id": first, "values": { "cmi.core.lesson_location": "p1" } },
+                { "attempt_id": missing, "values": { "cmi.core.lesson_location": "p9" } },
+                { "attempt_id": second, "values": { "cmi.core.lesson_location": "p2" } },
+            ] })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results[0]["ok"], true);
+        assert_eq!(results[1]["ok"], false);
+        assert_eq!(results[1]["attempt_id"], missing.to_string());
+        assert_eq!(results[2]["ok"], true);
+
+        for (id, location) in [(first, "p1"), (second, "p2")] {
+            let stored = repo.value(id, "cmi.core.lesson_location").await.unwrap();
+            assert_eq!(stored.as_deref(), Some(location));
+        }
+    }
+
+    #[tokio::test]
+    async fn courses_filter_by_a_shared_tag() {
+        let (app, repo) = test_app(&[]);
+        let (a, _) = seed_course(&repo, &["index.html"]).await;
+        let (b, _) = seed_course(&repo, &["index.html"]).await;
+        let (c, _) = seed_course(&repo, &["index.html"]).await;
+        for (course, tags) in [
+            (a.id, vec!["Compliance", "onboarding"]),
+            (b.id, vec!["compliance "]),
+            (c.id, vec!["onboarding"]),
+        ] {
+            let (status, body) = send(
+                &app,
+                Method::POST,
+                &format!("/api/courses/{course}/tags"),
+                Some(serde_json::json!({ "tags": tags })),
+                true,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+        }
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            "/api/courses?tag=COMPLIANCE",
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let mut ids: Vec<_> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        let mut expected = vec![a.id.to_string(), b.id.to_string()];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let (status, body) = send(
+            &app,
+            Method::DELETE,
+            &format!("/api/courses/{}/tags", a.id),
+            Some(serde_json::json!({ "tags": ["compliance"] })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["tags"], serde_json::json!(["onboarding"]));
+        let (_, body) = send(
+            &app,
+            Method::GET,
+            "/api/courses?tag=compliance",
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn default_sco_parameters_reach_the_launch_url() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="i0" identifierref="r0" parameters="?mode=strict"><title>Lesson</title></item>
+</organization></organizations>
+<resources><resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="index.html?lang=en"/></resources>
+</manifest>"#;
+        let (app, _) = test_app(&[]);
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("index.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+
+        let attempt_id = launch(&app, course_id, "learner-1").await;
+        let html = player_page(&app, attempt_id).await;
+        let launch_url = format!("/content/courses/{course_id}/index.html?lang=en&mode=strict");
+        assert!(html.contains(&launch_url), "{html}");
+    }
+
+    #[tokio::test]
+    async fn reassigned_attempt_reseeds_learner_identity() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "wrong-learner").await;
+        initialize(&app, attempt_id).await;
+
+        let reassign = serde_json::json!({ "learner_id": "learner-7", "learner_name": "Doe, Sam" });
+        let uri = format!("/api/attempts/{attempt_id}/reassign");
+        let (status, _) = send(&app, Method::POST, &uri, Some(reassign.clone()), false).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(&app, Method::POST, &uri, Some(reassign.clone()), true).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["learner_id"], "learner-7");
+        assert_eq!(body["learner_name"], "Doe, Sam");
+
+        let values = initialize(&app, attempt_id).await;
+        assert_eq!(values["cmi.core.student_id"], "learner-7");
+        assert_eq!(values["cmi.core.student_name"], "Doe, Sam");
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_status": "completed" }),
+        )
+        .await;
+        finish(&app, attempt_id).await;
+        let (status, _) = send(&app, Method::POST, &uri, Some(reassign), true).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn upload_can_pin_a_non_default_organization() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="full">
+<organization identifier="full"><title>Full</title>
+<item identifier="i0" identifierref="r0"><title>Full course</title></item></organization>
+<organization identifier="refresher"><title>Refresher</title>
+<item identifier="i1" identifierref="r1"><title>Refresher</title></item></organization>
+</organizations>
+<resources>
+<resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="full.html"/>
+<resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="refresher.html"/>
+</resources></manifest>"#;
+        let zip = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("full.html", b"<html></html>"),
+            ("refresher.html", b"<html></html>"),
+        ]);
+        let (app, _) = test_app(&[]);
+
+        let (status, body) = upload(&app, zip.clone(), &[]).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["launch_href"], "full.html");
+
+        let (status, body) = upload(
+            &app,
+            zip.clone(),
+            &[("organization_identifier", "refresher")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["org_identifier"], "refresher");
+        assert_eq!(body["launch_href"], "refresher.html");
+
+        let (status, body) = upload(&app, zip, &[("organization_identifier", "missing")]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "organization not found in manifest: missing");
+    }
+
+    #[tokio::test]
+    async fn get_answers_children_keywords() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let uri = format!("/runtime/{attempt_id}/get");
+
+        for (element, expected) in [
+            ("cmi.core.score._children", "raw,min,max"),
+            ("cmi.objectives._children", "id,score,status"),
+            ("cmi.core.lesson_status", ""),
+        ] {
+            let (status, body) = send(
+                &app,
+                Method::POST,
+                &uri,
+                Some(serde_json::json!({ "element": element })),
+                false,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            assert_eq!(body["value"], expected, "{element}");
+        }
+        let html = player_page(&app, attempt_id).await;
+        assert!(html.contains(r#""cmi.core.score._children":"raw,min,max""#));
+    }
+
+    #[tokio::test]
+    async fn oversized_upload_gets_a_json_413() {
+        // the global limit is layered in main.rs; mirror it around the router
+        let limit = 1024;
+        let (app, _) = test_app(&[]);
+        let app =
+            app.layer(DefaultBodyLimit::max(limit))
+                .layer(middleware::map_response_with_state(
+                    limit,
+                    payload_too_large_json,
+                ));
+
+        let manifest = single_sco_manifest("index.html");
+        // random bytes, so deflate cannot shrink the entry under the limit
+        let big: Vec<u8> = (0..limit / 4)
+            .flat_map(|_| Uuid::new_v4().into_bytes())
+            .collect();
+        let zip = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("index.html", &big),
+        ]);
+        let (status, body) = upload(&app, zip, &[]).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["error"]["code"], "payload_too_large");
+        assert_eq!(body["error"]["limit_bytes"], limit);
+        assert_eq!(
+            body["error"]["message"],
+            "request body exceeds the 1024 byte limit"
+        );
+    }
+
+    #[tokio::test]
+    async fn content_serves_byte_ranges() {
+        let (app, _) = test_app(&[]);
+        let manifest = single_sco_manifest("index.html");
+        let media: Vec<u8> = (0..=255u8).collect();
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("index.html", b"<html></html>"),
+                ("media/clip.mp4", &media),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let req = Request::get(format!(
+            "/content/courses/{}/media/clip.mp4",
+            body["id"].as_str().unwrap()
+        ))
+        .header(header::RANGE, "bytes=16-31")
+        .body(Body::empty())
+        .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers()[header::ACCEPT_RANGES], "bytes");
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 16-31/256");
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], &media[16..32]);
+    }
+
+    #[tokio::test]
+    async fn attempts_beyond_the_course_cap_are_rejected() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::PUT,
+            &format!("/api/courses/{}/max_attempts", course.id),
+            Some(serde_json::json!({ "max_attempts": 2 })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        for _ in 0..2 {
+            let attempt_id = launch(&app, course.id, "learner-1").await;
+            finish(&app, attempt_id).await;
+        }
+        let req = serde_json::json!({ "course_id": course.id, "learner_id": "learner-1" });
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(req.clone()),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            body,
+            "learner learner-1 has used all 2 attempts for this course"
+        );
+
+        // other learners and admins are not affected
+        launch(&app, course.id, "learner-2").await;
+        let (status, body) = send(&app, Method::POST, "/api/attempts", Some(req), true).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(
+            repo.learner_attempts(course.id, "learner-1")
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn strip_bom_assets_removes_the_bom_from_served_scripts() {
+        let script = b"\xEF\xBB\xBFvar ready = true;";
+        for (vars, expected) in [
+            (&[("STRIP_BOM_ASSETS", "true")][..], &script[3..]),
+            (&[][..], &script[..]),
+        ] {
+            let (app, _) = test_app(vars);
+            let manifest = single_sco_manifest("index.html");
+            let (status, body) = upload(
+                &app,
+                zip_of(&[
+                    ("imsmanifest.xml", manifest.as_bytes()),
+                    ("index.html", b"<html></html>"),
+                    ("js/app.js", script),
+                ]),
+                &[],
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+
+            let req = Request::get(format!(
+                "/content/courses/{}/js/app.js",
+                body["id"].as_str().unwrap()
+            ))
+            .body(Body::empty())
+            .unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(&bytes[..], expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn set_sco_switches_to_the_scos_own_attempt() {
+        let (app, repo) = test_app(&[]);
+        let (course, scos) = seed_course(&repo, &["one.html", "two.html"]).await;
+        let (_, other_scos) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let uri = format!("/runtime/{attempt_id}/set_sco");
+        let set_sco = |sco_id: Uuid| {
+            send(
+                &app,
+                Method::POST,
+                &uri,
+                Some(serde_json::json!({ "sco_id": sco_id })),
+                false,
+            )
+        };
+
+        let (status, body) = set_sco(scos[1].id).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["sco_id"], scos[1].id.to_string());
+        assert_eq!(body["identifier"], "i1");
+        assert_eq!(
+            body["launch_url"],
+            format!("/content/{}/two.html", course.base_path)
+        );
+        let target: Uuid = body["attempt_id"].as_str().unwrap().parse().unwrap();
+        assert_ne!(target, attempt_id);
+
+        // the calling attempt keeps its SCO; the stored SCO is on the target attempt
+        let launched = repo.attempt(attempt_id).await.unwrap().unwrap();
+        assert_eq!(launched.sco_id, None);
+        let stored = repo.attempt(target).await.unwrap().unwrap();
+        assert_eq!(stored.sco_id, Some(scos[1].id));
+        assert_eq!(stored.registration_id, launched.registration_id);
+
+        // revisiting reuses attempts; the default SCO maps back to the launch attempt
+        let (_, body) = set_sco(scos[1].id).await;
+        assert_eq!(body["attempt_id"], target.to_string());
+        let (_, body) = set_sco(scos[0].id).await;
+        assert_eq!(body["attempt_id"], attempt_id.to_string());
+        assert_eq!(
+            repo.registration_attempts(launched.registration_id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let (status, _) = set_sco(other_scos[0].id).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn transcode_html_serves_legacy_pages_as_utf8() {
+        let (app, _) = test_app(&[("TRANSCODE_HTML", "true")]);
+        let manifest = single_sco_manifest("index.html");
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                (
+                    "index.html",
+                    b"<html><head></head><body>Caf\xe9</body></html>",
+                ),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!(
+                "/content/courses/{}/index.html",
+                body["id"].as_str().unwrap()
+            ),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            "<html><head><meta charset=\"utf-8\"></head><body>Café</body></html>"
+        );
+    }
+
+    /// Log sink for asserting on tracing output.
+    #[derive(Clone, Default)]
+    struct LogBuf(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogBuf {
+        /// Route this thread's tracing output here until the guard drops.
+        fn capture(&self) -> tracing::subscriber::DefaultGuard {
+            let sink = self.clone();
+            tracing::subscriber::set_default(
+                tracing_subscriber::fmt()
+                    .with_ansi(false)
+                    .with_writer(move || sink.clone())
+                    .finish(),
+            )
+        }
+
+        fn contents(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_content_gets_a_page_and_a_warning() {
+        let logs = LogBuf::default();
+        let _guard = logs.capture();
+        let (app, _) = test_app(&[]);
+        let course_id = Uuid::new_v4();
+
+        let req = Request::get(format!("/content/courses/{course_id}/img/gone.png"))
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(res.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<h1>Content not found</h1>"), "{body}");
+        assert!(
+            body.contains(&format!("<code>/courses/{course_id}/img/gone.png</code>")),
+            "{body}"
+        );
+
+        let logs = logs.contents();
+        assert!(logs.contains("WARN"), "{logs}");
+        assert!(logs.contains("content asset not found"), "{logs}");
+        assert!(
+            logs.contains(&format!("base_path=courses/{course_id}")),
+            "{logs}"
+        );
+    }
+
+    #[tokio::test]
+    async fn raw_manifest_is_served_as_xml() {
+        let (app, repo) = test_app(&[]);
+        let manifest = single_sco_manifest("index.html");
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("index.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let req = Request::get(format!(
+            "/api/courses/{}/imsmanifest.xml",
+            body["id"].as_str().unwrap()
+        ))
+        .body(Body::empty())
+        .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("application/xml"));
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(bytes, manifest.as_bytes());
+
+        // seeded courses have no extracted files
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, _) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{}/imsmanifest.xml", course.id),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn patch_rejects_an_unknown_status() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (status, body) = send(
+            &app,
+            Method::PATCH,
+            &format!("/api/attempts/{attempt_id}"),
+            Some(serde_json::json!({ "status": "done" })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "unknown status: done");
+        let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "in_progress");
+    }
+
+    /// Serve `body` as `content_type` at any path on a loopback port; returns the base URL.
+    async fn mock_server(body: Vec<u8>, content_type: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().fallback(move || {
+            let body = body.clone();
+            async move { ([(header::CONTENT_TYPE, content_type)], body) }
+        });
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn upload_from_url_fetches_an_allowlisted_host() {
+        let manifest = single_sco_manifest("index.html");
+        let zip = zip_of(&[
+            ("imsmanifest.xml", manifest.as_bytes()),
+            ("index.html", b"<html></html>"),
+        ]);
+        let base = mock_server(zip, "application/zip").await;
+        let (app, repo) = test_app(&[("UPLOAD_URL_ALLOWLIST", "127.0.0.1")]);
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/courses/upload_from_url",
+            Some(serde_json::json!({ "title": "Remote", "url": format!("{base}/pkg/course.zip") })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["title"], "Remote");
+        assert_eq!(body["launch_href"], "index.html");
+        assert_eq!(repo.list_courses().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn upload_from_url_rejects_private_addresses() {
+        let base = mock_server(Vec::new(), "application/zip").await;
+        let (app, repo) = test_app(&[]);
+        for url in [
+            format!("{base}/course.zip"),
+            "http://10.0.0.8/course.zip".into(),
+        ] {
+            let (status, body) = send(
+                &app,
+                Method::POST,
+                "/api/courses/upload_from_url",
+                Some(serde_json::json!({ "url": url })),
+                false,
+            )
+            .await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{url}");
+            assert!(
+                body.as_str().unwrap().contains("non-public address"),
+                "{body}"
+            );
+        }
+        assert!(repo.list_courses().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn slow_requests_time_out_except_within_the_upload_budget() {
+        use std::time::Duration;
+        let slow = || async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            "done"
+        };
+        let timeouts = Timeouts {
+            default: Duration::from_millis(50),
+            upload: Duration::from_secs(5),
+        };
+        let app = Router::new()
+            .route("/slow", get(slow))
+            .route("/api/courses/upload", post(slow))
+            .layer(middleware::from_fn_with_state(timeouts, request_timeout));
+
+        let (status, _) = send(&app, Method::GET, "/slow", None, false).await;
+        assert_eq!(status, StatusCode::REQUEST_TIMEOUT);
+        let (status, body) = send(&app, Method::POST, "/api/courses/upload", None, false).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "done");
+    }
+
+    #[tokio::test]
+    async fn malformed_interaction_times_are_rejected() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({
+                "cmi.interactions.0.latency": "00:01:30",
+                "cmi.interactions.0.time": "14:05:00",
+                "cmi.interactions.1.latency": "90 seconds",
+                "cmi.interactions.1.time": "2:05 PM",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(
+            body["errors"],
+            serde_json::json!({
+                "cmi.interactions.1.latency": "405",
+                "cmi.interactions.1.time": "405",
+            })
+        );
+        let stored = repo
+            .value(attempt_id, "cmi.interactions.0.latency")
+            .await
+            .unwrap();
+        assert_eq!(stored.as_deref(), Some("00:01:30"));
+        let stored = repo
+            .value(attempt_id, "cmi.interactions.1.time")
+            .await
+            .unwrap();
+        assert_eq!(stored, None);
+    }
+
+    #[tokio::test]
+    async fn pass_threshold_overrides_the_manifest_mastery_score() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="i0" identifierref="r0"><title>Quiz</title><adlcp:masteryscore>70</adlcp:masteryscore></item>
+</organization></organizations>
+<resources><resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="index.html"/></resources>
+</manifest>"#;
+        let (app, _) = test_app(&[]);
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("index.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["mastery_score"], 70.0);
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+
+        let mut learner = 0;
+        let mut lesson_status_for_75 = || {
+            learner += 1;
+            let app = app.clone();
+            async move {
+                let attempt_id = launch(&app, course_id, &format!("learner-{learner}")).await;
+                let (_, body) = send(
+                    &app,
+                    Method::POST,
+                    &format!("/runtime/{attempt_id}/commit?echo=true"),
+                    Some(serde_json::json!({
+                        "cmi.core.lesson_status": "completed",
+                        "cmi.core.score.raw": "75",
+                    })),
+                    false,
+                )
+                .await;
+                body["values"]["cmi.core.lesson_status"].clone()
+            }
+        };
+        assert_eq!(lesson_status_for_75().await, "passed");
+
+        let uri = format!("/api/courses/{course_id}/pass_threshold");
+        let (status, body) = send(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(serde_json::json!({ "pass_threshold": 80 })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(lesson_status_for_75().await, "failed");
+
+        let (status, _) = send(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(serde_json::json!({ "pass_threshold": null })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(lesson_status_for_75().await, "passed");
+    }
+
+    #[tokio::test]
+    async fn dedup_keeps_one_blob_per_shared_file() {
+        use sha2::{Digest, Sha256};
+        let blob_dir = temp_path();
+        let (app, _) = test_app(&[
+            ("DEDUP_FILES", "true"),
+            ("BLOB_DIR", blob_dir.to_str().unwrap()),
+        ]);
+        let manifest = single_sco_manifest("index.html");
+        let library = b"window.lib = {};";
+        let mut course_ids = Vec::new();
+        for page in ["<p>one</p>", "<p>two</p>"] {
+            let (status, body) = upload(
+                &app,
+                zip_of(&[
+                    ("imsmanifest.xml", manifest.as_bytes()),
+                    ("index.html", page.as_bytes()),
+                    ("lib/runtime.js", library),
+                ]),
+                &[],
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            course_ids.push(body["id"].as_str().unwrap().to_string());
+        }
+
+        // manifest and library are shared, the pages differ
+        assert_eq!(std::fs::read_dir(&blob_dir).unwrap().count(), 4);
+        let hash: String = Sha256::digest(library)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert!(blob_dir.join(&hash).is_file());
+
+        for id in &course_ids {
+            let uri = format!("/content/courses/{id}/lib/runtime.js");
+            let (status, body) = send(&app, Method::GET, &uri, None, false).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body, "window.lib = {};");
+        }
+        // the store itself is not under /content
+        let (status, _) = send(
+            &app,
+            Method::GET,
+            &format!("/content/blobs/{hash}"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn language_preference_seeds_the_student_preference() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "language": "fr-CA",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["language"], "fr-CA");
+        let attempt_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+
+        let values = initialize(&app, attempt_id).await;
+        assert_eq!(values[runtime::LANGUAGE_12], "fr-CA");
+
+        // read-write: the learner's own choice wins on the next session
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ runtime::LANGUAGE_12: "en" }),
+        )
+        .await;
+        let values = initialize(&app, attempt_id).await;
+        assert_eq!(values[runtime::LANGUAGE_12], "en");
+    }
+
+    #[tokio::test]
+    async fn next_recommends_the_first_open_sco_with_met_prerequisites() {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="a" identifierref="ra"><title>A</title></item>
+<item identifier="b" identifierref="rb"><title>B</title>
+  <adlcp:prerequisites type="aicc_script">c</adlcp:prerequisites></item>
+<item identifier="c" identifierref="rc"><title>C</title></item>
+</organization></organizations>
+<resources>
+<resource identifier="ra" type="webcontent" adlcp:scormtype="sco" href="a.html"/>
+<resource identifier="rb" type="webcontent" adlcp:scormtype="sco" href="b.html"/>
+<resource identifier="rc" type="webcontent" adlcp:scormtype="sco" href="c.html"/>
+</resources></manifest>"#;
+        let (app, repo) = test_app(&[]);
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("a.html", b"<html></html>"),
+                ("b.html", b"<html></html>"),
+                ("c.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+        let scos = repo.scos_for_course(course_id).await.unwrap();
+
+        let first = launch(&app, course_id, "learner-1").await;
+        let next = |attempt_id: Uuid| {
+            let app = app.clone();
+            async move {
+                let (status, body) = send(
+                    &app,
+                    Method::GET,
+                    &format!("/api/attempts/{attempt_id}/next"),
+                    None,
+                    false,
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK, "{body}");
+                body
+            }
+        };
+        assert_eq!(next(first).await["next"]["identifier"], "a");
+
+        let done = serde_json::json!({ "cmi.core.lesson_status": "completed" });
+        commit(&app, first, done.clone()).await;
+        finish(&app, first).await;
+        // b waits for c
+        let body = next(first).await;
+        assert_eq!(body["complete"], false);
+        assert_eq!(body["next"]["identifier"], "c");
+        assert_eq!(body["next"]["sco_id"], scos[2].id.to_string());
+
+        let registration = repo.attempt(first).await.unwrap().unwrap().registration_id;
+        for sco in &scos[1..] {
+            let attempt = launch_sco(&app, course_id, sco.id, Some(registration)).await;
+            let attempt_id = attempt["id"].as_str().unwrap().parse().unwrap();
+            commit(&app, attempt_id, done.clone()).await;
+            finish(&app, attempt_id).await;
+        }
+        let body = next(first).await;
+        assert_eq!(body["complete"], true);
+        assert!(body["next"].is_null());
+    }
+
+    #[tokio::test]
+    async fn recommitting_a_value_keeps_its_updated_at() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let updated_at = |values: Vec<CmiValue>| {
+            values
+                .into_iter()
+                .find(|v| v.element == "cmi.core.lesson_location")
+                .unwrap()
+                .updated_at
+        };
+
+        let location = serde_json::json!({ "cmi.core.lesson_location": "p1" });
+        commit(&app, attempt_id, location.clone()).await;
+        let first = updated_at(repo.values(attempt_id).await.unwrap());
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        commit(&app, attempt_id, location).await;
+        assert_eq!(updated_at(repo.values(attempt_id).await.unwrap()), first);
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p2" }),
+        )
+        .await;
+        assert!(updated_at(repo.values(attempt_id).await.unwrap()) > first);
+    }
+
+    #[tokio::test]
+    async fn sco_param_name_passes_the_identifier_to_the_launch_url() {
+        let (app, repo) = test_app(&[("SCO_PARAM_NAME", "sco")]);
+        let (course, scos) = seed_course(&repo, &["main.html", "main.html?part=2"]).await;
+
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let html = player_page(&app, attempt_id).await;
+        assert!(
+            html.contains(&format!("/content/{}/main.html?sco=i0", course.base_path)),
+            "{html}"
+        );
+
+        let attempt = launch_sco(&app, course.id, scos[1].id, None).await;
+        let html = player_page(&app, attempt["id"].as_str().unwrap().parse().unwrap()).await;
+        assert!(
+            html.contains(&format!(
+                "/content/{}/main.html?part=2&sco=i1",
+                course.base_path
+            )),
+            "{html}"
+        );
+
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["main.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        assert!(!player_page(&app, attempt_id).await.contains("sco=i0"));
+    }
+
+    #[tokio::test]
+    async fn completion_reports_the_best_of_a_learners_attempts() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let uri = format!(
+            "/api/completions?course_id={}&learner_id=learner-1",
+            course.id
+        );
+
+        let (status, body) = send(&app, Method::GET, &uri, None, false).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["completed"], false);
+        assert!(body["best_status"].is_null());
+
+        let suspended = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            suspended,
+            serde_json::json!({ "cmi.core.lesson_status": "incomplete", "cmi.core.score.raw": "95" }),
+        )
+        .await;
+        send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{suspended}/suspend"),
+            None,
+            false,
+        )
+        .await;
+        let (_, body) = send(&app, Method::GET, &uri, None, false).await;
+        assert_eq!(body["completed"], false);
+        assert_eq!(body["best_status"], "incomplete");
+
+        let mut finished_at = Vec::new();
+        for (lesson_status, raw) in [("failed", "55"), ("passed", "80")] {
+            let attempt_id = launch(&app, course.id, "learner-1").await;
+            commit(
+                &app,
+                attempt_id,
+                serde_json::json!({ "cmi.core.lesson_status": lesson_status, "cmi.core.score.raw": raw }),
+            )
+            .await;
+            finish(&app, attempt_id).await;
+            finished_at.push(repo.attempt(attempt_id).await.unwrap().unwrap().finished_at);
+        }
+
+        let (_, body) = send(&app, Method::GET, &uri, None, false).await;
+        assert_eq!(body["completed"], true);
+        assert_eq!(body["best_status"], "passed");
+        assert_eq!(body["success"], "passed");
+        assert_eq!(body["best_score"], 95.0);
+        assert_eq!(body["completed_at"], serde_json::json!(finished_at[0]));
+    }
+
+    #[tokio::test]
+    async fn oversized_commit_is_rejected_with_a_json_413() {
+        let (app, repo) = test_app(&[("RUNTIME_BODY_LIMIT", "1024")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({ "cmi.suspend_data": "x".repeat(2048) })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{body}");
+        assert_eq!(body["error"]["code"], "payload_too_large");
+        assert_eq!(body["error"]["limit_bytes"], 1024);
+        assert!(repo.values(attempt_id).await.unwrap().is_empty());
+
+        // a commit under the limit still goes through
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.suspend_data": "x".repeat(512) }),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn mobile_devices_launch_the_mobile_variant() {
+        let data_dir = temp_path();
+        let (app, _) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+        let manifest = single_sco_manifest("lesson/index.html");
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("lesson/index.html", b"<html></html>"),
+                ("lesson/index.mobile.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+        let attempt_id = launch(&app, course_id, "learner-1").await;
+        let desktop = format!("/content/courses/{course_id}/lesson/index.html");
+        let mobile = format!("/content/courses/{course_id}/lesson/index.mobile.html");
+
+        let page = |query: &'static str, ua: Option<&'static str>| {
+            let mut req = Request::get(format!("/player/{attempt_id}{query}"));
+            if let Some(ua) = ua {
+                req = req.header(header::USER_AGENT, ua);
+            }
+            let app = app.clone();
+            async move {
+                let res = app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+                assert_eq!(res.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                String::from_utf8(bytes.to_vec()).unwrap()
+            }
+        };
+        let iphone = Some("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148");
+
+        let html = page("?device=mobile", None).await;
+        assert!(html.contains(&mobile), "{html}");
+        let html = page("", iphone).await;
+        assert!(html.contains(&mobile), "{html}");
+        let html = page("", None).await;
+        assert!(html.contains(&desktop) && !html.contains(&mobile), "{html}");
+        // an explicit hint wins over the User-Agent
+        let html = page("?device=desktop", iphone).await;
+        assert!(html.contains(&desktop) && !html.contains(&mobile), "{html}");
+    }
+
+    #[tokio::test]
+    async fn mis_cased_elements_are_stored_canonically_in_lenient_mode() {
+        for (flag, accepted) in [("false", false), ("true", true)] {
+            let (app, repo) = test_app(&[("CASE_INSENSITIVE_ELEMENTS", flag)]);
+            let (course, _) = seed_course(&repo, &["index.html"]).await;
+            let attempt_id = launch(&app, course.id, "learner-1").await;
+
+            let (_, body) = send(
+                &app,
+                Method::POST,
+                &format!("/runtime/{attempt_id}/commit"),
+                Some(serde_json::json!({ "cmi.core.Lesson_Status": "passed" })),
+                false,
+            )
+            .await;
+            assert_eq!(body["ok"], accepted, "{flag}: {body}");
+            let canonical = repo
+                .value(attempt_id, "cmi.core.lesson_status")
+                .await
+                .unwrap();
+            assert_eq!(canonical.is_some(), accepted, "{flag}");
+            let as_sent = repo
+                .value(attempt_id, "cmi.core.Lesson_Status")
+                .await
+                .unwrap();
+            assert!(as_sent.is_none(), "{flag}");
+        }
+    }
+
+    #[tokio::test]
+    async fn finish_returns_the_final_rollup() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "return_url": "https://lms.example.com/done",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let attempt_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({
+                "cmi.core.lesson_status": "passed",
+                "cmi.core.score.raw": "85",
+                "cmi.core.score.min": "0",
+                "cmi.core.score.max": "100",
+                "cmi.core.session_time": "0000:01:30",
+            }),
+        )
+        .await;
+
+        let body = finish(&app, attempt_id).await;
+        assert_eq!(body["ok"], true);
+        assert_eq!(body["status"], "completed");
+        assert_eq!(body["completion"], "completed");
+        assert_eq!(body["success"], "passed");
+        assert_eq!(body["score_raw"], 85.0);
+        assert_eq!(body["score_scaled"], 0.85);
+        assert_eq!(body["total_time"], "0000:01:30");
+        assert_eq!(body["return_url"], "https://lms.example.com/done");
+    }
+
+    #[tokio::test]
+    async fn files_endpoint_lists_launch_file_and_declared_assets() {
+        let data_dir = temp_path();
+        let (app, _) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="I1" identifierref="R1"><title>Lesson</title></item></organization></organizations>
+<resources>
+  <resource identifier="R1" type="webcontent" adlcp:scormtype="sco" href="sco1/index.html">
+    <file href="sco1/index.html"/><file href="sco1/style.css"/><dependency identifierref="SHARED"/>
+  </resource>
+  <resource identifier="SHARED" type="webcontent" adlcp:scormtype="asset">
+    <file href="shared/a.js"/>
+  </resource>
+</resources></manifest>"#;
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("sco1/index.html", b"<html></html>"),
+                ("sco1/style.css", b""),
+                ("shared/a.js", b""),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id = body["id"].as_str().unwrap();
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{course_id}/files"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(
+            body["resources"],
+            serde_json::json!([
+                {
+                    "identifier": "R1",
+                    "href": "sco1/index.html",
+                    "scos": ["I1"],
+                    "files": ["sco1/index.html", "sco1/style.css", "shared/a.js"],
+                },
+                { "identifier": "SHARED", "href": null, "scos": [], "files": ["shared/a.js"] },
+            ])
+        );
+
+        let (status, _) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{}/files", Uuid::new_v4()),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn finish_after_uncommitted_sets_keeps_the_flushed_cache() {
+        for (flag, flushes) in [("true", true), ("false", false)] {
+            let (app, repo) = test_app(&[("PLAYER_COMMIT_ON_FINISH", flag)]);
+            let (course, _) = seed_course(&repo, &["index.html"]).await;
+            let attempt_id = launch(&app, course.id, "learner-1").await;
+            let html = player_page(&app, attempt_id).await;
+            assert!(
+                html.contains(&format!("const flushed = {flag} ? post('commit', cache)")),
+                "{flag}"
+            );
+            if !flushes {
+                continue;
+            }
+
+            // what the shim sends for LMSSetValue calls followed by LMSFinish alone
+            commit(
+                &app,
+                attempt_id,
+                serde_json::json!({
+                    "cmi.core.lesson_status": "passed",
+                    "cmi.core.score.raw": "72",
+                }),
+            )
+            .await;
+            let body = finish(&app, attempt_id).await;
+            assert_eq!(body["success"], "passed");
+            assert_eq!(body["score_raw"], 72.0);
+            let stored = repo.value(attempt_id, "cmi.core.score.raw").await.unwrap();
+            assert_eq!(stored.as_deref(), Some("72"));
+        }
+    }
+
+    #[tokio::test]
+    async fn rapid_commits_past_the_write_limit_get_429() {
+        let (app, repo) = test_app(&[("RUNTIME_WRITES_PER_SECOND", "3")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let other = launch(&app, course.id, "learner-2").await;
+        let values = serde_json::json!({ "cmi.core.lesson_location": "p1" });
+
+        for _ in 0..3 {
+            commit(&app, attempt_id, values.clone()).await;
+        }
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(values.clone()),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{body}");
+        // another attempt has its own bucket
+        commit(&app, other, values).await;
+    }
+
+    #[tokio::test]
+    async fn strict_href_rejects_absolute_launch_urls() {
+        let manifest = single_sco_manifest("https://external.example.com/course.html");
+        let zip = zip_of(&[("imsmanifest.xml", manifest.as_bytes())]);
+        for (strict, ok) in [("true", false), ("false", true)] {
+            let data_dir = temp_path();
+            let (app, repo) = test_app(&[
+                ("STRICT_HREF", strict),
+                ("DATA_DIR", data_dir.to_str().unwrap()),
+            ]);
+            let (status, body) = upload(&app, zip.clone(), &[]).await;
+            if ok {
+                assert_eq!(status, StatusCode::OK, "{body}");
+                let course_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+                let attempt_id = launch(&app, course_id, "learner-1").await;
+                let html = player_page(&app, attempt_id).await;
+                assert!(
+                    html.contains(
+                        r#"const launchUrl = "https://external.example.com/course.html";"#
+                    ),
+                    "{html}"
+                );
+                assert!(html.contains("frame-src 'self' https://external.example.com;"));
+            } else {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(
+                    body,
+                    "launch href is an absolute URL (STRICT_HREF): https://external.example.com/course.html"
+                );
+                assert_eq!(stored_uploads(&data_dir), 0);
+                assert!(repo.list_courses().await.unwrap().is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn patching_learner_name_leaves_other_fields_untouched() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_status": "passed" }),
+        )
+        .await;
+        finish(&app, attempt_id).await;
+        let uri = format!("/api/attempts/{attempt_id}");
+        let (_, before) = send(&app, Method::GET, &uri, None, true).await;
+
+        let (status, after) = send(
+            &app,
+            Method::PATCH,
+            &uri,
+            Some(serde_json::json!({ "learner_name": "  Ada Lovelace " })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{after}");
+        assert_eq!(after["learner_name"], "Ada Lovelace");
+        let (mut before, mut after) = (before, after);
+        for v in [&mut before, &mut after] {
+            v.as_object_mut().unwrap().remove("learner_name");
+        }
+        assert_eq!(before, after);
+
+        let (status, _) = send(
+            &app,
+            Method::PATCH,
+            &uri,
+            Some(serde_json::json!({ "status": "in_progress" })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send(
+            &app,
+            Method::PATCH,
+            &uri,
+            Some(serde_json::json!({ "learner_name": "x" })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    /// Uploads intro (hidden), one, two; `one` and `two` carry launch data.
+    async fn upload_strategy_course(app: &Router) -> Uuid {
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="intro" identifierref="r0" isvisible="false"><title>Intro</title></item>
+<item identifier="one" identifierref="r1"><title>One</title><adlcp:datafromlms>one-data</adlcp:datafromlms></item>
+<item identifier="two" identifierref="r2"><title>Two</title><adlcp:datafromlms>two-data</adlcp:datafromlms></item>
+</organization></organizations>
+<resources>
+<resource identifier="r0" type="webcontent" adlcp:scormtype="sco" href="intro.html"/>
+<resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="one.html"/>
+<resource identifier="r2" type="webcontent" adlcp:scormtype="sco" href="two.html"/>
+</resources></manifest>"#;
+        let (status, body) = upload(
+            app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("intro.html", b"<html></html>"),
+                ("one.html", b"<html></html>"),
+                ("two.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn default_launch_strategy_picks_the_sco() {
+        let data_dir = temp_path();
+        let (app, repo) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+        let course_id = upload_strategy_course(&app).await;
+        let two = repo
+            .scos_for_course(course_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|s| s.identifier == "two")
+            .unwrap();
+        // an earlier attempt in the registration that ran SCO "two"
+        let earlier = launch_sco(&app, course_id, two.id, None).await;
+        let registration_id = earlier["registration_id"].as_str().unwrap();
+
+        for (strategy, href) in [
+            ("manifest_default", "intro.html"),
+            ("first_sco", "one.html"),
+            ("resume", "two.html"),
+        ] {
+            let (status, body) = send(
+                &app,
+                Method::PUT,
+                &format!("/api/courses/{course_id}/default_launch_strategy"),
+                Some(serde_json::json!({ "default_launch_strategy": strategy })),
+                true,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            let (status, body) = send(
+                &app,
+                Method::POST,
+                "/api/attempts",
+                Some(serde_json::json!({
+                    "course_id": course_id,
+                    "learner_id": "learner-1",
+                    "registration_id": registration_id,
+                })),
+                false,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            let attempt_id: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+            let html = player_page(&app, attempt_id).await;
+            let url = format!(r#"const launchUrl = "/content/courses/{course_id}/{href}""#);
+            assert!(html.contains(&url), "{strategy}: {html}");
+        }
+
+        let (status, _) = send(
+            &app,
+            Method::PUT,
+            &format!("/api/courses/{course_id}/default_launch_strategy"),
+            Some(serde_json::json!({ "default_launch_strategy": "random" })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn default_launch_strategy_drives_the_runtime_too() {
+        let (app, repo) = test_app(&[]);
+        let course_id = upload_strategy_course(&app).await;
+        let scos = repo.scos_for_course(course_id).await.unwrap();
+        let set_strategy = |strategy: &'static str| {
+            let app = app.clone();
+            async move {
+                let (status, body) = send(
+                    &app,
+                    Method::PUT,
+                    &format!("/api/courses/{course_id}/default_launch_strategy"),
+                    Some(serde_json::json!({ "default_launch_strategy": strategy })),
+                    true,
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK, "{body}");
+            }
+        };
+
+        // first_sco skips the hidden intro: launch data, nav and rollup all see "one"
+        set_strategy("first_sco").await;
+        let first = launch(&app, course_id, "learner-1").await;
+        assert_eq!(initialize(&app, first).await["cmi.launch_data"], "one-data");
+        commit(
+            &app,
+            first,
+            serde_json::json!({
+                "cmi.core.lesson_status": "completed",
+                "adl.nav.request": "continue",
+            }),
+        )
+        .await;
+        let body = finish(&app, first).await;
+        assert_eq!(body["nav"]["identifier"], "two");
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/attempts/{first}/next"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["next"]["identifier"], "two");
+
+        // resume: a SCO-less attempt after one on "two" runs "two"
+        set_strategy("resume").await;
+        let earlier = launch_sco(&app, course_id, scos[2].id, None).await;
+        let earlier_id: Uuid = earlier["id"].as_str().unwrap().parse().unwrap();
+        finish(&app, earlier_id).await;
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course_id,
+                "learner_id": "learner-1",
+                "registration_id": earlier["registration_id"],
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let resumed: Uuid = body["id"].as_str().unwrap().parse().unwrap();
+        assert_ne!(resumed, earlier_id);
+        assert_eq!(
+            initialize(&app, resumed).await["cmi.launch_data"],
+            "two-data"
+        );
+        commit(
+            &app,
+            resumed,
+            serde_json::json!({ "adl.nav.request": "previous" }),
+        )
+        .await;
+        let body = finish(&app, resumed).await;
+        assert_eq!(body["nav"]["identifier"], "one");
+    }
+
+    #[tokio::test]
+    async fn export_zips_the_uploaded_course_back_up() {
+        use std::io::Read;
+        let blob_dir = temp_path();
+        let (app, _) = test_app(&[
+            ("DEDUP_FILES", "true"),
+            ("BLOB_DIR", blob_dir.to_str().unwrap()),
+        ]);
+        let manifest = single_sco_manifest("lesson/index.html");
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("lesson/index.html", b"<p>hi</p>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id = body["id"].as_str().unwrap();
+
+        let req = Request::get(format!("/api/courses/{course_id}/export.zip"))
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes.to_vec())).unwrap();
+        let mut read = |name: &str| {
+            let mut s = String::new();
+            zip.by_name(name).unwrap().read_to_string(&mut s).unwrap();
+            s
+        };
+        assert_eq!(read("imsmanifest.xml"), manifest);
+        // deduplicated files are exported with their contents, not as links
+        assert_eq!(read("lesson/index.html"), "<p>hi</p>");
+
+        let (status, _) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{}/export.zip", Uuid::new_v4()),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn complete_on_score_finalizes_score_only_attempts() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let status_after_commit = |values: serde_json::Value| {
+            let (app, repo) = (app.clone(), repo.clone());
+            async move {
+                let attempt_id = launch(&app, course.id, "learner-1").await;
+                commit(&app, attempt_id, values).await;
+                let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+                let lesson = repo
+                    .value(attempt_id, "cmi.core.lesson_status")
+                    .await
+                    .unwrap();
+                (attempt.status, lesson)
+            }
+        };
+        let score_only = serde_json::json!({ "cmi.core.score.raw": "80" });
+
+        // off by default: the attempt waits for a status that never comes
+        let (status, lesson) = status_after_commit(score_only.clone()).await;
+        assert_eq!((status.as_str(), lesson), ("in_progress", None));
+
+        for (path, body) in [
+            (
+                "pass_threshold",
+                serde_json::json!({ "pass_threshold": 70 }),
+            ),
+            (
+                "complete_on_score",
+                serde_json::json!({ "complete_on_score": true }),
+            ),
+        ] {
+            let uri = format!("/api/courses/{}/{path}", course.id);
+            let (status, body) = send(&app, Method::PUT, &uri, Some(body), true).await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+        }
+        for (values, lesson_status, attempt_status) in [
+            (score_only, "passed", "completed"),
+            (
+                serde_json::json!({ "cmi.core.score.raw": "50" }),
+                "failed",
+                "failed",
+            ),
+            // an explicit status is never overridden
+            (
+                serde_json::json!({ "cmi.core.score.raw": "80", "cmi.core.lesson_status": "incomplete" }),
+                "incomplete",
+                "in_progress",
+            ),
+        ] {
+            let (status, lesson) = status_after_commit(values).await;
+            assert_eq!(status, attempt_status, "{lesson_status}");
+            assert_eq!(lesson.as_deref(), Some(lesson_status));
+        }
+    }
+
+    #[tokio::test]
+    async fn player_exposes_the_api_where_the_frames_parent_search_looks() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let html = player_page(&app, attempt_id).await;
+
+        // the SCO frame is a direct child of the page defining window.API, and
+        // starts empty so no content script runs before the API exists
+        assert!(html.contains("<iframe id='sco'></iframe>"));
+        let api = html.find("window.API = {").expect("window.API defined");
+        let launched = html.rfind("launch(launchUrl);").expect("frame launched");
+        assert!(api < launched);
+        for method in [
+            "LMSInitialize",
+            "LMSFinish",
+            "LMSGetValue",
+            "LMSSetValue",
+            "LMSCommit",
+        ] {
+            assert!(
+                html[api..launched].contains(&format!("{method}(")),
+                "{method}"
+            );
+        }
+        // content that only checks its own window finds the API too
+        assert!(html.contains("e.target.contentWindow.API = window.API;"));
+    }
+
+    #[tokio::test]
+    async fn too_large_or_malformed_interaction_indices_get_201() {
+        let (app, repo) = test_app(&[("MAX_ARRAY_ENTRIES", "10")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            &format!("/runtime/{attempt_id}/commit"),
+            Some(serde_json::json!({
+                "cmi.interactions.9.id": "q9",
+                "cmi.interactions.10.id": "q10",
+                "cmi.interactions.07.id": "q7",
+                "cmi.interactions.-1.id": "qm",
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ok"], false);
+        assert_eq!(
+            body["errors"],
+            serde_json::json!({
+                "cmi.interactions.10.id": "201",
+                "cmi.interactions.07.id": "201",
+                "cmi.interactions.-1.id": "201",
+            })
+        );
+        let stored: Vec<_> = repo
+            .values(attempt_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| v.element)
+            .collect();
+        assert_eq!(stored, ["cmi.interactions.9.id"]);
+    }
+
+    #[tokio::test]
+    async fn diagnostics_report_sco_count_and_missing_files() {
+        let data_dir = temp_path();
+        let (app, _) = test_app(&[("DATA_DIR", data_dir.to_str().unwrap())]);
+        let manifest = r#"<manifest xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+<organizations default="o"><organization identifier="o"><title>T</title>
+<item identifier="i1" identifierref="r1"><title>One</title></item>
+<item identifier="i2" identifierref="r2"><title>Two</title></item>
+<item identifier="i3" identifierref="gone"><title>Dangling</title></item>
+</organization></organizations>
+<resources>
+<resource identifier="r1" type="webcontent" adlcp:scormtype="sco" href="one.html">
+  <file href="one.html"/><file href="js/missing.js"/>
+</resource>
+<resource identifier="r2" type="webcontent" adlcp:scormtype="sco" href="two.html"/>
+</resources></manifest>"#;
+        let (status, body) = upload(
+            &app,
+            zip_of(&[
+                ("imsmanifest.xml", manifest.as_bytes()),
+                ("one.html", b"<html></html>"),
+                ("two.html", b"<html></html>"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let course_id = body["id"].as_str().unwrap();
+
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{course_id}/diagnostics"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["sco_count"], 2);
+        assert_eq!(body["scorm_version"], "1.2");
+        assert_eq!(body["manifest"], "imsmanifest.xml");
+        assert_eq!(body["missing_files"], serde_json::json!(["js/missing.js"]));
+        let warnings = body["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1, "{body}");
+        assert!(warnings[0].as_str().unwrap().contains("gone"), "{body}");
+        assert_eq!(
+            body["launch_candidates"][0],
+            serde_json::json!({ "href": "one.html", "exists": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn attempt_metadata_round_trips() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let metadata =
+            serde_json::json!({ "assignment_id": "a-42", "cohort": { "term": "2026-spring" } });
+        let (status, created) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "metadata": metadata,
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{created}");
+        assert_eq!(created["metadata"], metadata);
+        let attempt_id = created["id"].as_str().unwrap();
+
+        let (status, detail) = send(
+            &app,
+            Method::GET,
+            &format!("/api/attempts/{attempt_id}"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(detail["metadata"], metadata);
+        let (status, grade) = send(
+            &app,
+            Method::GET,
+            &format!("/api/attempts/{attempt_id}/grade"),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(grade["metadata"], metadata);
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/api/attempts",
+            Some(serde_json::json!({
+                "course_id": course.id,
+                "learner_id": "learner-1",
+                "metadata": ["not", "an", "object"],
+            })),
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn audit_elements_limit_what_gets_logged() {
+        let (app, repo) = test_app(&[("AUDIT_ELEMENTS", "cmi.core.lesson_status")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({
+                "cmi.core.lesson_status": "incomplete",
+                "cmi.interactions.0.id": "q1",
+                "cmi.interactions.0.result": "correct",
+                "cmi.core.score.raw": "80",
+            }),
+        )
+        .await;
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({
+                "cmi.core.lesson_status": "browsed",
+                "cmi.interactions.1.id": "q2",
+            }),
+        )
+        .await;
+
+        // every value is stored; only the status writes are logged
+        assert_eq!(repo.values(attempt_id).await.unwrap().len(), 5);
+        let (status, body) = send(
+            &app,
+            Method::GET,
+            &format!("/api/courses/{}/events.jsonl", course.id),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let logged: Vec<serde_json::Value> = body
+            .as_str()
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let writes: Vec<_> = logged
+            .iter()
+            .map(|l| (l["element"].as_str().unwrap(), l["value"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            writes,
+            [
+                ("cmi.core.lesson_status", "incomplete"),
+                ("cmi.core.lesson_status", "browsed"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_player_template_gets_attempt_and_launch_url() {
+        let template = temp_path().with_extension("html");
+        std::fs::write(
+            &template,
+            "<main data-attempt='{{attempt_id}}'><script>const url = {{launch_url}};</script>{{unknown}}</main>",
+        )
+        .unwrap();
+        let (app, repo) = test_app(&[("PLAYER_TEMPLATE", template.to_str().unwrap())]);
+        let (course, _) = seed_course(&repo, &["lesson/index.html?p=1"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let html = player_page(&app, attempt_id).await;
+        assert_eq!(
+            html,
+            format!(
+                "<main data-attempt='{attempt_id}'><script>const url = \"/content/{}/lesson/index.html?p=1\";</script>{{{{unknown}}}}</main>",
+                course.id
+            )
+        );
+        std::fs::remove_file(&template).unwrap();
+    }
+
+    #[tokio::test]
+    async fn finish_reports_failed_and_suspended_from_the_cmi() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+
+        let failed = launch(&app, course.id, "learner-1").await;
+        commit(
+            &app,
+            failed,
+            serde_json::json!({ "cmi.core.lesson_status": "failed" }),
+        )
+        .await;
+        assert_eq!(finish(&app, failed).await["status"], "failed");
+        assert_eq!(
+            repo.attempt(failed).await.unwrap().unwrap().status,
+            "failed"
+        );
+
+        let suspended = launch(&app, course.id, "learner-2").await;
+        commit(
+            &app,
+            suspended,
+            serde_json::json!({ "cmi.core.lesson_status": "incomplete", "cmi.core.exit": "suspend" }),
+        )
+        .await;
+        finish(&app, suspended).await;
+        let attempt = repo.attempt(suspended).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "suspended");
+
+        // a final lesson_status wins over exit=suspend
+        let passed = launch(&app, course.id, "learner-3").await;
+        commit(
+            &app,
+            passed,
+            serde_json::json!({ "cmi.core.lesson_status": "passed", "cmi.core.exit": "suspend" }),
+        )
+        .await;
+        assert_eq!(finish(&app, passed).await["status"], "completed");
+        let attempt = repo.attempt(passed).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "completed");
+    }
+
+    #[tokio::test]
+    async fn locked_attempts_reject_writes_until_an_admin_reopens_them() {
+        let (app, repo) = test_app(&[("LOCK_FINISHED_ATTEMPTS", "true")]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+        let commit_uri = format!("/runtime/{attempt_id}/commit");
+        let reopen_uri = format!("/api/attempts/{attempt_id}/reopen");
+
+        // A suspended attempt stays writable.
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.exit": "suspend" }),
+        )
+        .await;
+        finish(&app, attempt_id).await;
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_location": "p2" }),
+        )
+        .await;
+        let (status, _) = send(&app, Method::POST, &reopen_uri, None, true).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        commit(
+            &app,
+            attempt_id,
+            serde_json::json!({ "cmi.core.lesson_status": "passed" }),
+        )
+        .await;
+        assert_eq!(
+            repo.attempt(attempt_id).await.unwrap().unwrap().status,
+            "completed"
+        );
+        let late = serde_json::json!({ "cmi.core.session_time": "00:01:00" });
+        let (status, _) = send(&app, Method::POST, &commit_uri, Some(late.clone()), false).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        for end in ["suspend", "finish"] {
+            let uri = format!("/runtime/{attempt_id}/{end}");
+            let (status, _) = send(&app, Method::POST, &uri, None, false).await;
+            assert_eq!(status, StatusCode::CONFLICT, "{end}");
+        }
+        let attempt = repo.attempt(attempt_id).await.unwrap().unwrap();
+        assert_eq!(attempt.status, "completed");
+
+        let (status, _) = send(
+            &app,
+            Method::PATCH,
+            &format!("/api/attempts/{attempt_id}"),
+            Some(serde_json::json!({ "status": "in_progress" })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = send(&app, Method::POST, &reopen_uri, None, false).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(&app, Method::POST, &reopen_uri, None, true).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["status"], "in_progress");
+        assert!(body["finished_at"].is_null());
+        let (status, body) = send(&app, Method::POST, &commit_uri, Some(late), false).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let unknown = format!("/api/attempts/{}/reopen", Uuid::new_v4());
+        let (status, _) = send(&app, Method::POST, &unknown, None, true).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn popup_launcher_and_bridge_check_origins_and_seed_reads() {
+        let (app, repo) = test_app(&[]);
+        let (course, _) = seed_course(&repo, &["index.html"]).await;
+        let (status, body) = send(
+            &app,
+            Method::PUT,
+            &format!("/api/courses/{}/launch_mode", course.id),
+            Some(serde_json::json!({ "launch_mode": "popup" })),
+            true,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let attempt_id = launch(&app, course.id, "learner-1").await;
+
+        let html = player_page(&app, attempt_id).await;
+        for needle in [
+            "const popupMode = 'popup' === 'popup'",
+            "addEventListener('message'",
+            "ev.source !== popup || ev.origin !== scoOrigin",
+            "m.type === 'hello'",
+            "m.type === 'call' && relayed.includes(m.method)",
+            "popup.postMessage({scorm:1, type:'init'",
+            "'scorm_init=' + snapshot",
+        ] {
+            assert!(html.contains(needle), "player lacks {needle}");
+        }
+
+        let (status, body) = send(&app, Method::GET, "/player/bridge.js", None, false).await;
+        assert_eq!(status, StatusCode::OK);
+        let js = body.as_str().unwrap();
+        assert_eq!(js, player::BRIDGE_JS);
+        for needle in [
+            "addEventListener('message'",
+            "ev.source !== opener || ev.origin !== playerOrigin",
+            "m.type === 'init'",
+            "opener.postMessage(Object.assign({scorm:1}, msg), playerOrigin)",
+            "send({type:'hello'})",
+            "location.hash.match(/(^#|&)scorm_init=([^&]*)$/)",
+        ] {
+            assert!(js.contains(needle), "bridge lacks {needle}");
+        }
+    }
+}
@@ -1,33 +1,671 @@
 // Minimal validators and helpers for SCORM 1.2
 
+use crate::models::AttemptStatus;
+
 pub fn is_valid_element_12(el: &str) -> bool {
     matches!(
         el,
         "cmi.core.lesson_status"
             | "cmi.core.lesson_location"
             | "cmi.core.score.raw"
+            | "cmi.core.score.min"
+            | "cmi.core.score.max"
             | "cmi.suspend_data"
             | "cmi.core.session_time"
             | "cmi.core.exit"
     )
 }
 
+/// Attempt status to record on finish. `cmi.core.exit = time-out` is a forced,
+/// terminal end and is kept distinct from a normal completion. A final
+/// lesson_status (passed, completed, failed) decides next, so `suspend` only
+/// leaves an attempt resumable while its content is unfinished.
+pub fn next_attempt_status(exit: Option<&str>, lesson_status: Option<&str>) -> AttemptStatus {
+    if exit == Some("time-out") {
+        return AttemptStatus::TimedOut;
+    }
+    match lesson_status.and_then(finished_status) {
+        Some(finished) => finished,
+        None if exit == Some("suspend") => AttemptStatus::Suspended,
+        None => AttemptStatus::Completed,
+    }
+}
+
+/// The attempt status a lesson_status closes an attempt with: `failed` for
+/// failed, `completed` for completed or passed; None while still open.
+pub fn finished_status(lesson_status: &str) -> Option<AttemptStatus> {
+    match lesson_status {
+        "completed" | "passed" => Some(AttemptStatus::Completed),
+        "failed" => Some(AttemptStatus::Failed),
+        _ => None,
+    }
+}
+
+/// Registration rollup: `completed` once every SCO has a completed attempt,
+/// `in_progress` once any launch happened, else `not_started`.
+pub fn registration_status(
+    total_scos: usize,
+    completed_scos: usize,
+    launched: bool,
+) -> &'static str {
+    if total_scos > 0 && completed_scos >= total_scos {
+        "completed"
+    } else if launched {
+        "in_progress"
+    } else {
+        "not_started"
+    }
+}
+
+/// Evaluate a 1.2 `adlcp:prerequisites` expression: item identifiers joined by
+/// `&`, `|` and `~` with parentheses, each true when `done(identifier)`.
+/// None for syntax outside that subset (e.g. the `n*{...}` set form).
+pub fn prerequisites_met(expr: &str, done: &dyn Fn(&str) -> bool) -> Option<bool> {
+    let mut tokens = Vec::new();
+    let mut ident = String::new();
+    for c in expr.chars() {
+        if matches!(c, '&' | '|' | '~' | '(' | ')') || c.is_whitespace() {
+            if !ident.is_empty() {
+                tokens.push(std::mem::take(&mut ident));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else if matches!(c, '*' | '{' | '}' | ',' | '=' | '<' | '>') {
+            return None;
+        } else {
+            ident.push(c);
+        }
+    }
+    if !ident.is_empty() {
+        tokens.push(ident);
+    }
+
+    // or := and ('|' and)* ; and := not ('&' not)* ; not := '~' not | '(' or ')' | ident
+    fn or(t: &[String], i: &mut usize, done: &dyn Fn(&str) -> bool) -> Option<bool> {
+        let mut v = and(t, i, done)?;
+        while t.get(*i).is_some_and(|x| x == "|") {
+            *i += 1;
+            v |= and(t, i, done)?;
+        }
+        Some(v)
+    }
+    fn and(t: &[String], i: &mut usize, done: &dyn Fn(&str) -> bool) -> Option<bool> {
+        let mut v = not(t, i, done)?;
+        while t.get(*i).is_some_and(|x| x == "&") {
+            *i += 1;
+            v &= not(t, i, done)?;
+        }
+        Some(v)
+    }
+    fn not(t: &[String], i: &mut usize, done: &dyn Fn(&str) -> bool) -> Option<bool> {
+        let tok = t.get(*i)?;
+        *i += 1;
+        match tok.as_str() {
+            "~" => not(t, i, done).map(|v| !v),
+            "(" => {
+                let v = or(t, i, done)?;
+                (t.get(*i)? == ")").then(|| *i += 1)?;
+                Some(v)
+            }
+            "&" | "|" | ")" => None,
+            id => Some(done(id)),
+        }
+    }
+
+    let mut i = 0;
+    let v = or(&tokens, &mut i, done)?;
+    (i == tokens.len()).then_some(v)
+}
+
+/// 1.2 mastery rule: once a SCO reports a finished status and a raw score,
+/// the LMS decides passed/failed against the mastery score.
+pub fn derive_lesson_status(status: &str, raw: Option<f64>, mastery: Option<f64>) -> &str {
+    match (raw, mastery) {
+        (Some(raw), Some(mastery)) if matches!(status, "completed" | "passed" | "failed") => {
+            if raw >= mastery {
+                "passed"
+            } else {
+                "failed"
+            }
+        }
+        _ => status,
+    }
+}
+
+/// Ordering of 1.2 lesson statuses for "best attempt" summaries; higher is better.
+pub fn lesson_status_rank(status: &str) -> u8 {
+    match status {
+        "passed" => 6,
+        "completed" => 5,
+        "failed" => 4,
+        "incomplete" => 3,
+        "browsed" => 2,
+        "not attempted" => 1,
+        _ => 0,
+    }
+}
+
+/// A 1.2 lesson_status in the 2004 reporting model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnifiedStatus {
+    // completed | incomplete | unknown
+    pub completion: &'static str,
+    // passed | failed | unknown
+    pub success: &'static str,
+}
+
+/// Map a 1.2 lesson_status onto (completion, success) so 1.2 and 2004 courses
+/// report alike. `browsed` (a no-credit review) is incomplete unless
+/// `browsed_completes`; `not attempted` and unset are unknown.
+pub fn unify_status(lesson_status: Option<&str>, browsed_completes: bool) -> UnifiedStatus {
+    let (completion, success) = match lesson_status {
+        Some("passed") => ("completed", "passed"),
+        Some("failed") => ("completed", "failed"),
+        Some("completed") => ("completed", "unknown"),
+        Some("browsed") if browsed_completes => ("completed", "unknown"),
+        Some("incomplete") | Some("browsed") => ("incomplete", "unknown"),
+        _ => ("unknown", "unknown"),
+    };
+    UnifiedStatus {
+        completion,
+        success,
+    }
+}
+
+/// Scaled score (0..1) from 1.2 raw/min/max; min/max default to 0/100.
+pub fn scaled_score(raw: Option<f64>, min: Option<f64>, max: Option<f64>) -> Option<f64> {
+    let raw = raw?;
+    let (min, max) = (min.unwrap_or(0.0), max.unwrap_or(100.0));
+    if max <= min {
+        return None;
+    }
+    Some(((raw - min) / (max - min)).clamp(0.0, 1.0))
+}
+
+/// Parse a 1.2 CMITimespan (`HHHH:MM:SS.SS`, hours 2-4 digits) into seconds.
+pub fn parse_timespan_12(v: &str) -> Option<f64> {
+    let mut parts = v.trim().split(':');
+    let (h, m, s) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || !(2..=4).contains(&h.len()) || m.len() != 2 {
+        return None;
+    }
+    let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+    if whole.len() != 2 || frac.len() > 2 {
+        return None;
+    }
+    let digits = |x: &str| !x.is_empty() && x.bytes().all(|b| b.is_ascii_digit());
+    if !digits(h) || !digits(m) || !digits(whole) || !(frac.is_empty() || digits(frac)) {
+        return None;
+    }
+    let (h, m): (f64, f64) = (h.parse().ok()?, m.parse().ok()?);
+    let s: f64 = s.parse().ok()?;
+    if m >= 60.0 || s >= 60.0 {
+        return None;
+    }
+    Some(h * 3600.0 + m * 60.0 + s)
+}
+
+/// Format seconds as a 1.2 CMITimespan, e.g. `0000:00:00` or `0001:02:03.45`.
+pub fn format_timespan_12(secs: f64) -> String {
+    let cs = (secs.max(0.0) * 100.0).round() as u64;
+    let (h, rem) = (cs / 360_000, cs % 360_000);
+    let (m, rem) = (rem / 6_000, rem % 6_000);
+    let (s, frac) = (rem / 100, rem % 100);
+    if frac == 0 {
+        format!("{:04}:{:02}:{:02}", h.min(9999), m, s)
+    } else {
+        format!("{:04}:{:02}:{:02}.{:02}", h.min(9999), m, s, frac)
+    }
+}
+
+/// SCORM 2004 navigation request element; stored and resolved on finish.
+pub const NAV_REQUEST: &str = "adl.nav.request";
+
+/// SCORM 2004 scaled score; when reported it wins over the 1.2 raw/min/max formula.
+pub const SCORE_SCALED: &str = "cmi.score.scaled";
+
+/// Elements the runtime persists: the 1.2 subset, 1.2 interactions, the
+/// language preference, `adl.nav.request` and `cmi.score.scaled`.
+pub fn is_writable_element(el: &str) -> bool {
+    is_valid_element_12(el)
+        || interaction_field(el).is_some()
+        || el == LANGUAGE_12
+        || el == LANGUAGE_2004
+        || el == NAV_REQUEST
+        || el == SCORE_SCALED
+}
+
+/// Canonical (all lowercase) spelling of a mis-cased data model element, for
+/// `CASE_INSENSITIVE_ELEMENTS`; None when `el` is already canonical or unknown.
+pub fn canonical_element(el: &str) -> Option<String> {
+    let lower = el.to_ascii_lowercase();
+    (lower != el && (is_writable_element(&lower) || is_read_only_element(&lower))).then_some(lower)
+}
+
+/// Learner language preference; read-write, seeded from the attempt.
+pub const LANGUAGE_12: &str = "cmi.student_preference.language";
+pub const LANGUAGE_2004: &str = "cmi.learner_preference.language";
+
+/// The language preference element for a course's `scorm_version`.
+pub fn language_element(scorm_version: &str) -> &'static str {
+    if scorm_version == "2004" {
+        LANGUAGE_2004
+    } else {
+        LANGUAGE_12
+    }
+}
+
+/// The field of a 1.2 interaction element (`cmi.interactions.n.<field>`), e.g.
+/// `latency` or `objectives.0.id`; None for anything else. Index positions only
+/// need to be non-empty here; `array_indices_valid` judges them.
+pub fn interaction_field(el: &str) -> Option<&str> {
+    let (n, field) = el.strip_prefix("cmi.interactions.")?.split_once('.')?;
+    let index = |x: &str| !x.is_empty();
+    let indexed = |list: &str, leaf: &str| {
+        field
+            .strip_prefix(list)
+            .and_then(|r| r.strip_prefix('.'))
+            .and_then(|r| r.split_once('.'))
+            .is_some_and(|(i, f)| index(i) && f == leaf)
+    };
+    let known = matches!(
+        field,
+        "id" | "time" | "type" | "weighting" | "student_response" | "result" | "latency"
+    ) || indexed("objectives", "id")
+        || indexed("correct_responses", "pattern");
+    (index(n) && known).then_some(field)
+}
+
+/// Every array index in an indexed element (`interactions.n`, `objectives.n`,
+/// `correct_responses.n`) is a plain non-negative integer (no sign, no leading
+/// zeros) below `max_entries`, bounding how large a SCO can grow an array.
+pub fn array_indices_valid(el: &str, max_entries: usize) -> bool {
+    let parts: Vec<&str> = el.split('.').collect();
+    parts.windows(2).all(|w| {
+        !matches!(w[0], "interactions" | "objectives" | "correct_responses") || {
+            let i = w[1];
+            !i.is_empty()
+                && i.bytes().all(|b| b.is_ascii_digit())
+                && (i == "0" || !i.starts_with('0'))
+                && i.parse::<usize>().is_ok_and(|n| n < max_entries)
+        }
+    })
+}
+
+/// Typed interaction fields: `latency` is a CMITimespan and `time` a CMITime.
+pub fn valid_interaction_value(field: &str, v: &str) -> bool {
+    match field {
+        "latency" => parse_timespan_12(v).is_some(),
+        "time" => is_valid_time_12(v),
+        _ => true,
+    }
+}
+
+/// 1.2 CMITime: `HH:MM:SS` with optional 1-2 digit hundredths, hours 00-23.
+pub fn is_valid_time_12(v: &str) -> bool {
+    let mut parts = v.split(':');
+    let (Some(h), Some(m), Some(s), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+    let two = |x: &str| x.len() == 2 && x.bytes().all(|b| b.is_ascii_digit());
+    let frac_ok = match s.contains('.') {
+        true => (1..=2).contains(&frac.len()) && frac.bytes().all(|b| b.is_ascii_digit()),
+        false => true,
+    };
+    two(h) && two(m) && two(whole) && frac_ok && h < "24" && m < "60" && whole < "60"
+}
+
+/// `cmi.score.scaled` must be a real number in -1..=1 (bounds included).
+pub fn parse_scaled_score(v: &str) -> Option<f64> {
+    v.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| (-1.0..=1.0).contains(n))
+}
+
+/// Per-attempt seed for content randomization, seeded on initialize.
+pub const RANDOM_SEED: &str = "x.random_seed";
+
+/// Final lesson status a SCO reported before the course's minimum session time;
+/// applied on finish once the minimum is met.
+pub const HELD_LESSON_STATUS: &str = "x.held_lesson_status";
+
+/// Elements the LMS owns (1.2 read-only set plus the `x.` elements); SCO writes
+/// get error 403 in every mode.
+pub fn is_read_only_element(el: &str) -> bool {
+    el == RANDOM_SEED
+        || el == HELD_LESSON_STATUS
+        || matches!(
+            el,
+            "cmi.core.student_id"
+                | "cmi.core.student_name"
+                | "cmi.core.credit"
+                | "cmi.core.entry"
+                | "cmi.core.total_time"
+                | "cmi.core.lesson_mode"
+                | "cmi.launch_data"
+                | "cmi.comments_from_lms"
+        )
+        || el.starts_with("cmi.student_data.")
+        || el.ends_with("._children")
+        || el.ends_with("._count")
+}
+
+/// SCORM 1.2 `_children` keywords and their comma-separated answers.
+pub const CHILDREN: &[(&str, &str)] = &[
+    (
+        "cmi.core._children",
+        "student_id,student_name,lesson_location,credit,lesson_status,entry,score,total_time,lesson_mode,exit,session_time",
+    ),
+    ("cmi.core.score._children", "raw,min,max"),
+    ("cmi.objectives._children", "id,score,status"),
+    (
+        "cmi.interactions._children",
+        "id,objectives,time,type,correct_responses,weighting,student_response,result,latency",
+    ),
+    ("cmi.student_data._children", "mastery_score,max_time_allowed,time_limit_action"),
+    ("cmi.student_preference._children", "audio,language,speed,text"),
+];
+
+/// Answer for a `_children` keyword, if it is one the runtime knows.
+pub fn children(el: &str) -> Option<&'static str> {
+    CHILDREN.iter().find(|(k, _)| *k == el).map(|(_, v)| *v)
+}
+
+/// How commits treat elements outside the supported set (`ELEMENT_MODE`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementMode {
+    /// Reject with 401 (not implemented).
+    Strict,
+    /// Store as-is under the default length cap, for custom data capture.
+    Permissive,
+}
+
+impl std::str::FromStr for ElementMode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(ElementMode::Strict),
+            "permissive" => Ok(ElementMode::Permissive),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Longest element name accepted for storage in permissive mode.
+pub const MAX_ELEMENT_NAME_LEN: usize = 255;
+
+/// Course-level allowlist check; entries are exact names or `prefix*`.
+pub fn element_allowed(el: &str, allowed: &[String]) -> bool {
+    allowed.iter().any(|a| match a.strip_suffix('*') {
+        Some(prefix) => el.starts_with(prefix),
+        None => a == el,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavRequest {
+    Continue,
+    Previous,
+    Choice(String), // target item identifier
+    Exit,
+    ExitAll,
+    Abandon,
+    AbandonAll,
+    SuspendAll,
+    None,
+}
+
+pub fn parse_nav_request(v: &str) -> Option<NavRequest> {
+    // choice carries its target as "{target=ITEM_ID}choice"
+    if let Some(rest) = v.strip_prefix("{target=") {
+        let (target, req) = rest.split_once('}')?;
+        return (req == "choice" && !target.is_empty())
+            .then(|| NavRequest::Choice(target.to_string()));
+    }
+    match v {
+        "continue" => Some(NavRequest::Continue),
+        "previous" => Some(NavRequest::Previous),
+        "exit" => Some(NavRequest::Exit),
+        "exitAll" => Some(NavRequest::ExitAll),
+        "abandon" => Some(NavRequest::Abandon),
+        "abandonAll" => Some(NavRequest::AbandonAll),
+        "suspendAll" => Some(NavRequest::SuspendAll),
+        "_none_" => Some(NavRequest::None),
+        _ => None,
+    }
+}
+
+/// SCORM 1.2 API error codes reported for rejected writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScormError {
+    GeneralException,
+    InvalidArgument,
+    NotImplemented,
+    ReadOnly,
+    IncorrectDataType,
+}
+
+impl ScormError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScormError::GeneralException => "101",
+            ScormError::InvalidArgument => "201",
+            ScormError::NotImplemented => "401",
+            ScormError::ReadOnly => "403",
+            ScormError::IncorrectDataType => "405",
+        }
+    }
+}
+
 pub fn max_len(el: &str) -> usize {
     match el {
         "cmi.suspend_data" => 4096, // common de facto 1.2 limit
+        "cmi.comments_from_lms" => 4096,
         _ => 255,
     }
 }
 
 pub fn normalize_lesson_status(v: &str) -> Option<&'static str> {
     match v {
-        "passed"        => Some("passed"),
-        "failed"        => Some("failed"),
-        "completed"     => Some("completed"),
-        "incomplete"    => Some("incomplete"),
-        "browsed"       => Some("browsed"),
+        "passed" => Some("passed"),
+        "failed" => Some("failed"),
+        "completed" => Some("completed"),
+        "incomplete" => Some("incomplete"),
+        "browsed" => Some("browsed"),
         "not attempted" => Some("not attempted"),
         _ => None,
     }
 }
 
+// --- suspend_data compression at rest ---
+
+/// base64(gzip(v)), stored with `cmi_values.compressed` set.
+pub fn compress_value(v: &str) -> String {
+    use base64::Engine;
+    use std::io::Write;
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    // writing into a Vec cannot fail
+    enc.write_all(v.as_bytes()).expect("gzip into memory");
+    let gz = enc.finish().expect("gzip into memory");
+    base64::engine::general_purpose::STANDARD.encode(gz)
+}
+
+/// Inverse of `compress_value`; undecodable values pass through.
+pub fn decompress_value(v: String) -> String {
+    use base64::Engine;
+    use std::io::Read;
+    let Ok(gz) = base64::engine::general_purpose::STANDARD.decode(&v) else {
+        return v;
+    };
+    let mut out = String::new();
+    match flate2::read::GzDecoder::new(&gz[..]).read_to_string(&mut out) {
+        Ok(_) => out,
+        Err(_) => v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_and_lesson_status_pick_the_finished_status() {
+        for (exit, lesson_status, expected) in [
+            (Some("time-out"), Some("passed"), AttemptStatus::TimedOut),
+            (
+                Some("suspend"),
+                Some("incomplete"),
+                AttemptStatus::Suspended,
+            ),
+            (Some("suspend"), None, AttemptStatus::Suspended),
+            (Some("suspend"), Some("failed"), AttemptStatus::Failed),
+            (Some("suspend"), Some("passed"), AttemptStatus::Completed),
+            (Some("logout"), Some("failed"), AttemptStatus::Failed),
+            (Some(""), Some("passed"), AttemptStatus::Completed),
+            (None, Some("incomplete"), AttemptStatus::Completed),
+            (None, None, AttemptStatus::Completed),
+        ] {
+            assert_eq!(
+                next_attempt_status(exit, lesson_status),
+                expected,
+                "{exit:?} {lesson_status:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_final_lesson_statuses_close_an_attempt() {
+        assert_eq!(finished_status("passed"), Some(AttemptStatus::Completed));
+        assert_eq!(finished_status("completed"), Some(AttemptStatus::Completed));
+        assert_eq!(finished_status("failed"), Some(AttemptStatus::Failed));
+        for open in ["incomplete", "browsed", "not attempted", ""] {
+            assert_eq!(finished_status(open), None, "{open}");
+        }
+    }
+
+    #[test]
+    fn scaled_score_accepts_only_minus_one_to_one() {
+        for ok in ["-1", "0", "1", " 0.5 ", "-0.25"] {
+            assert!(parse_scaled_score(ok).is_some(), "{ok}");
+        }
+        for bad in ["-1.0001", "1.5", "2", "abc", ""] {
+            assert_eq!(parse_scaled_score(bad), None, "{bad}");
+        }
+        assert_eq!(ScormError::IncorrectDataType.code(), "405");
+    }
+
+    #[test]
+    fn children_keywords_answer_their_supported_fields() {
+        for (keyword, expected) in [
+            (
+                "cmi.core._children",
+                "student_id,student_name,lesson_location,credit,lesson_status,entry,score,total_time,lesson_mode,exit,session_time",
+            ),
+            ("cmi.core.score._children", "raw,min,max"),
+            ("cmi.objectives._children", "id,score,status"),
+            (
+                "cmi.interactions._children",
+                "id,objectives,time,type,correct_responses,weighting,student_response,result,latency",
+            ),
+            ("cmi.student_data._children", "mastery_score,max_time_allowed,time_limit_action"),
+            ("cmi.student_preference._children", "audio,language,speed,text"),
+        ] {
+            assert_eq!(children(keyword), Some(expected), "{keyword}");
+            assert!(is_read_only_element(keyword));
+        }
+        assert_eq!(children("cmi.core.score"), None);
+        assert_eq!(children("cmi.suspend_data._children"), None);
+    }
+
+    #[test]
+    fn interaction_latency_and_time_are_typed() {
+        for ok in ["00:00:05", "0001:02:03.4", "9999:59:59.99"] {
+            assert!(valid_interaction_value("latency", ok), "{ok}");
+        }
+        for bad in [
+            "5",
+            "0:00:05",
+            "00:60:00",
+            "00:00:05.123",
+            "00:00:5",
+            "aa:bb:cc",
+        ] {
+            assert!(!valid_interaction_value("latency", bad), "{bad}");
+        }
+        for ok in ["00:00:00", "23:59:59", "09:30:15.5", "09:30:15.25"] {
+            assert!(valid_interaction_value("time", ok), "{ok}");
+        }
+        for bad in [
+            "24:00:00",
+            "9:30:15",
+            "09:60:00",
+            "09:30:15.",
+            "09:30:15.123",
+            "09:30",
+        ] {
+            assert!(!valid_interaction_value("time", bad), "{bad}");
+        }
+        assert!(valid_interaction_value("student_response", "anything"));
+        assert_eq!(
+            interaction_field("cmi.interactions.0.latency"),
+            Some("latency")
+        );
+    }
+
+    #[test]
+    fn language_element_follows_the_scorm_version() {
+        assert_eq!(language_element("2004"), "cmi.learner_preference.language");
+        assert_eq!(language_element("1.2"), "cmi.student_preference.language");
+    }
+
+    #[test]
+    fn unify_status_maps_every_lesson_status() {
+        let pair = |s: Option<&str>, browsed_completes| {
+            let u = unify_status(s, browsed_completes);
+            (u.completion, u.success)
+        };
+        for (status, expected) in [
+            (Some("passed"), ("completed", "passed")),
+            (Some("failed"), ("completed", "failed")),
+            (Some("completed"), ("completed", "unknown")),
+            (Some("incomplete"), ("incomplete", "unknown")),
+            (Some("browsed"), ("incomplete", "unknown")),
+            (Some("not attempted"), ("unknown", "unknown")),
+            (Some("bogus"), ("unknown", "unknown")),
+            (None, ("unknown", "unknown")),
+        ] {
+            assert_eq!(pair(status, false), expected, "{status:?}");
+        }
+        assert_eq!(pair(Some("browsed"), true), ("completed", "unknown"));
+        assert_eq!(pair(Some("not attempted"), true), ("unknown", "unknown"));
+    }
+
+    #[test]
+    fn array_indices_must_be_plain_and_bounded() {
+        for el in [
+            "cmi.interactions.0.id",
+            "cmi.interactions.999.objectives.0.id",
+            "cmi.interactions.10.correct_responses.999.pattern",
+            "cmi.core.lesson_status",
+        ] {
+            assert!(array_indices_valid(el, 1000), "{el}");
+        }
+        for el in [
+            "cmi.interactions.1000.id",
+            "cmi.interactions.999999.id",
+            "cmi.interactions.0.objectives.1000.id",
+            "cmi.interactions.01.id",
+            "cmi.interactions.-1.id",
+            "cmi.interactions.+1.id",
+            "cmi.interactions.1e3.id",
+            "cmi.interactions..id",
+        ] {
+            assert!(!array_indices_valid(el, 1000), "{el}");
+        }
+    }
+}
@@ -4,3 +4,206 @@ pub fn url_encode(s: &str) -> String {
     utf8_percent_encode(s, NON_ALPHANUMERIC).to_string()
 }
 
+/// Join URL path segments with exactly one `/` between them, collapsing any
+/// duplicate slashes. The result always starts with `/`; a query or fragment
+/// (on the last segment) is kept verbatim.
+pub fn join_url_path(segments: &[&str]) -> String {
+    let joined = segments.join("/");
+    let (path, rest) = joined.split_at(joined.find(['?', '#']).unwrap_or(joined.len()));
+    let mut out = String::new();
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        out.push('/');
+        out.push_str(part);
+    }
+    if out.is_empty() {
+        out.push('/');
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Append a manifest item's `parameters` to a launch URL (IMS CP rules): a
+/// leading `?`/`&` is dropped and the rest joined with `?` or `&` as needed,
+/// ahead of any fragment; a `#fragment` parameter is added only if the URL has none.
+pub fn with_launch_parameters(url: &str, parameters: Option<&str>) -> String {
+    let params = parameters.map(str::trim).unwrap_or("");
+    if params.is_empty() {
+        return url.to_string();
+    }
+    if let Some(fragment) = params.strip_prefix('#') {
+        return if url.contains('#') || fragment.is_empty() {
+            url.to_string()
+        } else {
+            format!("{}#{}", url, fragment)
+        };
+    }
+    let params = params.trim_start_matches(['?', '&']);
+    let (base, fragment) = url.split_at(url.find('#').unwrap_or(url.len()));
+    let sep = if !base.contains('?') {
+        "?"
+    } else if base.ends_with(['?', '&']) {
+        ""
+    } else {
+        "&"
+    };
+    format!("{}{}{}{}", base, sep, params, fragment)
+}
+
+/// True for an absolute URL (`scheme:...`) or a scheme-relative `//host/...`
+/// reference, i.e. an href that points outside the package.
+pub fn is_absolute_url(href: &str) -> bool {
+    let href = href.trim();
+    if href.starts_with("//") {
+        return true;
+    }
+    // a one-letter "scheme" is a Windows drive, not a URL
+    match href.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// True when `p` is relative and never climbs out of its root (no `..`, no absolute parts).
+pub fn is_safe_relative_path(p: &str) -> bool {
+    use std::path::Component;
+    std::path::Path::new(p)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Download filename for a course package: the title slugified to `[a-z0-9-]`,
+/// so it is safe to put in a quoted Content-Disposition.
+pub fn package_filename(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.chars().take(80).collect();
+    let slug = slug.trim_end_matches('-');
+    format!("{}.zip", if slug.is_empty() { "course" } else { slug })
+}
+
+/// Rough phone/tablet detection from a User-Agent header.
+pub fn is_mobile_user_agent(ua: &str) -> bool {
+    ["Mobi", "Android", "iPhone", "iPad", "iPod"]
+        .iter()
+        .any(|m| ua.contains(m))
+}
+
+/// Minimal HTML escaping for values interpolated into generated markup/attributes.
+pub fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_filename_is_a_safe_slug() {
+        assert_eq!(
+            package_filename(r#"Intro / "Safety" \ 101"#),
+            "intro-safety-101.zip"
+        );
+        assert_eq!(package_filename("../../etc/passwd"), "etc-passwd.zip");
+        assert_eq!(package_filename("\"//\""), "course.zip");
+        assert_eq!(package_filename(&"a".repeat(200)).len(), 80 + ".zip".len());
+    }
+
+    #[test]
+    fn join_url_path_has_single_slashes_at_boundaries() {
+        assert_eq!(
+            join_url_path(&["content", "courses/abc", "index.html"]),
+            "/content/courses/abc/index.html"
+        );
+        assert_eq!(
+            join_url_path(&["/content/", "/courses/abc/", "/index.html"]),
+            "/content/courses/abc/index.html"
+        );
+        assert_eq!(
+            join_url_path(&["content//", "courses//abc", "lesson//index.html"]),
+            "/content/courses/abc/lesson/index.html"
+        );
+        assert_eq!(join_url_path(&["", "/", ""]), "/");
+        // query and fragment are not touched
+        assert_eq!(
+            join_url_path(&["content", "a", "index.html?next=//x#//y"]),
+            "/content/a/index.html?next=//x#//y"
+        );
+    }
+
+    #[test]
+    fn launch_parameters_join_the_query_ahead_of_the_fragment() {
+        assert_eq!(with_launch_parameters("a.html", None), "a.html");
+        assert_eq!(with_launch_parameters("a.html", Some("  ")), "a.html");
+        assert_eq!(
+            with_launch_parameters("a.html", Some("?mode=strict")),
+            "a.html?mode=strict"
+        );
+        assert_eq!(
+            with_launch_parameters("a.html?x=1", Some("&mode=strict")),
+            "a.html?x=1&mode=strict"
+        );
+        assert_eq!(
+            with_launch_parameters("a.html?", Some("mode=strict")),
+            "a.html?mode=strict"
+        );
+        assert_eq!(
+            with_launch_parameters("a.html#top", Some("mode=strict")),
+            "a.html?mode=strict#top"
+        );
+        assert_eq!(
+            with_launch_parameters("a.html", Some("#intro")),
+            "a.html#intro"
+        );
+        assert_eq!(
+            with_launch_parameters("a.html#top", Some("#intro")),
+            "a.html#top"
+        );
+    }
+
+    #[test]
+    fn absolute_urls_are_told_apart_from_package_paths() {
+        for href in [
+            "http://example.com/a.html",
+            " HTTPS://example.com/a.html",
+            "//cdn.example.com/a.html",
+            "javascript:alert(1)",
+            "data:text/html,hi",
+        ] {
+            assert!(is_absolute_url(href), "{href}");
+        }
+        for href in [
+            "index.html",
+            "sco1/index.html?x=http://a",
+            "/abs/path.html",
+            "C:/course/index.html",
+            "1http:x",
+            "page.html#a:b",
+            "",
+        ] {
+            assert!(!is_absolute_url(href), "{href}");
+        }
+    }
+}